@@ -0,0 +1,259 @@
+//! Exponential ElGamal on Ristretto for encrypted ballots.
+//!
+//! A count `m` is encrypted to the poll key `Y` as `(C1, C2) = (r·G, m·G + r·Y)`.
+//! Ciphertexts add component-wise, so the sum of every ballot's ciphertext for
+//! a candidate encrypts that candidate's tally.
+//!
+//! The poll key is shared `t`-of-`n` by a joint Feldman key generation. Trustee
+//! `i` picks a secret polynomial `f_i` of degree `t − 1`, registers commitments
+//! `C_ik = a_ik·G` to its coefficients with a proof of knowledge of `a_i0`, and
+//! privately sends `f_i(j)` to trustee `j`, who checks it against
+//! `Σ_k j^k·C_ik`. Trustee `j` (numbered from 1) holds `s_j = Σ_i f_i(j)`, with
+//! verification key `Y_j = Σ_k j^k·A_k` for `A_k = Σ_i C_ik`, and the poll key
+//! is `Y = A_0`. Any `t` trustees post `D_j = s_j·C1`, and `C2 − Σλ_j·D_j` is
+//! `m·G` for their Lagrange coefficients `λ_j`, from which the small tally `m`
+//! is recovered off-chain and checked here.
+//!
+//! Proofs are Fiat-Shamir sigma protocols. A challenge is the sha256 of a
+//! domain tag, an optional context and the listed points, with the top four
+//! bits cleared so it is a canonical scalar. Provers follow the transcripts
+//! documented on each `verify_*` function; this module only verifies.
+
+use anchor_lang::solana_program::hash::hashv;
+use solana_curve25519::ristretto::{
+    add_ristretto, multiply_ristretto, multiscalar_multiply_ristretto, subtract_ristretto, PodRistrettoPoint,
+};
+use solana_curve25519::scalar::PodScalar;
+
+use crate::{BitProof, Ciphertext, DleqProof, SchnorrProof};
+
+/// The compressed Ristretto basepoint `G`.
+pub const BASEPOINT: [u8; 32] = [
+    226, 242, 174, 10, 106, 188, 78, 113, 168, 132, 169, 97, 197, 0, 81, 95, 88, 227, 11, 106, 165, 130, 221, 141,
+    182, 166, 89, 69, 224, 141, 45, 118,
+];
+
+/// The compressed identity point, the encryption of nothing.
+pub const IDENTITY: [u8; 32] = [0; 32];
+
+const BIT_DOMAIN: &[u8] = b"voting:elgamal:bit";
+const SUM_DOMAIN: &[u8] = b"voting:elgamal:sum";
+const SHARE_DOMAIN: &[u8] = b"voting:elgamal:share";
+const KEY_DOMAIN: &[u8] = b"voting:elgamal:key";
+
+// The group order `l`, as little-endian 64-bit limbs.
+const ORDER: [u64; 4] = [0x5812631a5cf5d3ed, 0x14def9dea2f79cd6, 0, 0x1000000000000000];
+
+/// `a + b`, or `None` if either is not a valid point.
+pub fn add(a: &[u8; 32], b: &[u8; 32]) -> Option<[u8; 32]> {
+    add_ristretto(&PodRistrettoPoint(*a), &PodRistrettoPoint(*b)).map(|point| point.0)
+}
+
+fn sub(a: &[u8; 32], b: &[u8; 32]) -> Option<[u8; 32]> {
+    subtract_ristretto(&PodRistrettoPoint(*a), &PodRistrettoPoint(*b)).map(|point| point.0)
+}
+
+/// `scalar·point`. Fails on a non-canonical scalar as well as a bad point.
+fn mul(scalar: &[u8; 32], point: &[u8; 32]) -> Option<[u8; 32]> {
+    multiply_ristretto(&PodScalar(*scalar), &PodRistrettoPoint(*point)).map(|point| point.0)
+}
+
+/// `z·P − c·Q`, the commitment a verifier recomputes from a response `z`.
+fn commitment(z: &[u8; 32], p: &[u8; 32], c: &[u8; 32], q: &[u8; 32]) -> Option<[u8; 32]> {
+    sub(&mul(z, p)?, &mul(c, q)?)
+}
+
+/// A small integer as a scalar.
+fn scalar(value: u128) -> [u8; 32] {
+    let mut scalar = [0u8; 32];
+    scalar[..16].copy_from_slice(&value.to_le_bytes());
+    scalar
+}
+
+fn challenge(domain: &[u8], context: &[u8], points: &[&[u8; 32]]) -> [u8; 32] {
+    let mut parts: Vec<&[u8]> = vec![domain, context];
+    parts.extend(points.iter().map(|point| point.as_slice()));
+    let mut challenge = hashv(&parts).to_bytes();
+    challenge[31] &= 0x0f;
+    challenge
+}
+
+fn limbs(scalar: &[u8; 32]) -> [u64; 4] {
+    core::array::from_fn(|i| u64::from_le_bytes(scalar[i * 8..i * 8 + 8].try_into().unwrap()))
+}
+
+/// `a + b mod l` for canonical scalars `a` and `b`.
+fn add_scalars(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (a, b) = (limbs(a), limbs(b));
+    // Both are below `l < 2^253`, so the sum cannot carry out of the top limb.
+    let mut sum = [0u64; 4];
+    let mut carry = false;
+    for i in 0..4 {
+        let (partial, overflow) = a[i].overflowing_add(b[i]);
+        let (partial, carried) = partial.overflowing_add(carry as u64);
+        sum[i] = partial;
+        carry = overflow || carried;
+    }
+    if sum.iter().rev().ge(ORDER.iter().rev()) {
+        let mut borrow = false;
+        for i in 0..4 {
+            let (partial, underflow) = sum[i].overflowing_sub(ORDER[i]);
+            let (partial, borrowed) = partial.overflowing_sub(borrow as u64);
+            sum[i] = partial;
+            borrow = underflow || borrowed;
+        }
+    }
+    let mut bytes = [0u8; 32];
+    for (chunk, limb) in bytes.chunks_exact_mut(8).zip(sum) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    bytes
+}
+
+/// Trustee `index`'s verification key `Σ_k index^k·A_k`, from the summed
+/// coefficient commitments `A_k`.
+pub fn verification_key(commitments: &[[u8; 32]], index: u64) -> Option<[u8; 32]> {
+    let mut power = 1u128;
+    let mut scalars = Vec::with_capacity(commitments.len());
+    for _ in commitments {
+        scalars.push(PodScalar(scalar(power)));
+        power = power.checked_mul(index as u128)?;
+    }
+    let points: Vec<PodRistrettoPoint> = commitments.iter().map(|commitment| PodRistrettoPoint(*commitment)).collect();
+    multiscalar_multiply_ristretto(&scalars, &points).map(|point| point.0)
+}
+
+pub fn add_ciphertexts(a: &Ciphertext, b: &Ciphertext) -> Option<Ciphertext> {
+    Some(Ciphertext {
+        c1: add(&a.c1, &b.c1)?,
+        c2: add(&a.c2, &b.c2)?,
+    })
+}
+
+/// Whether `proof` shows knowledge of the secret behind `key`.
+///
+/// The trustee commits `a = w·G` and answers `z = w + c·a_0`, where
+/// `c = H(key, context, C, a)`; the context names the trustee and poll so the
+/// proof cannot be replayed by another.
+pub fn verify_key(context: &[u8], key: &[u8; 32], proof: &SchnorrProof) -> bool {
+    commitment(&proof.z, &BASEPOINT, &proof.c, key).is_some_and(|a| challenge(KEY_DOMAIN, context, &[key, &a]) == proof.c)
+}
+
+/// Whether `ciphertext` encrypts 0 or 1 under `key`.
+///
+/// A disjunction of two equality proofs: branch `j` shows `C1 = r·G` and
+/// `C2 − j·G = r·Y`. The prover simulates the false branch with random `c_k`,
+/// `z_k`, commits `a_j = w·G`, `b_j = w·Y` for the true one, and answers
+/// `c_j = c − c_k`, `z_j = w + c_j·r`, where
+/// `c = H(bit, voter, Y, C1, C2, a0, b0, a1, b1)`.
+pub fn verify_bit(key: &[u8; 32], voter: &[u8], ciphertext: &Ciphertext, proof: &BitProof) -> bool {
+    bit_challenge(key, voter, ciphertext, proof).is_some_and(|c| c == add_scalars(&proof.c0, &proof.c1))
+}
+
+fn bit_challenge(key: &[u8; 32], voter: &[u8], ciphertext: &Ciphertext, proof: &BitProof) -> Option<[u8; 32]> {
+    let shifted = sub(&ciphertext.c2, &BASEPOINT)?;
+    let a0 = commitment(&proof.z0, &BASEPOINT, &proof.c0, &ciphertext.c1)?;
+    let b0 = commitment(&proof.z0, key, &proof.c0, &ciphertext.c2)?;
+    let a1 = commitment(&proof.z1, &BASEPOINT, &proof.c1, &ciphertext.c1)?;
+    let b1 = commitment(&proof.z1, key, &proof.c1, &shifted)?;
+    Some(challenge(BIT_DOMAIN, voter, &[key, &ciphertext.c1, &ciphertext.c2, &a0, &b0, &a1, &b1]))
+}
+
+/// Whether `sum`, the sum of a ballot's ciphertexts, encrypts exactly 1.
+///
+/// Shows `C1 = R·G` and `C2 − G = R·Y` for the summed randomness `R`: the
+/// prover commits `a = w·G`, `b = w·Y` and answers `z = w + c·R`, where
+/// `c = H(sum, voter, Y, C1, C2, a, b)`.
+pub fn verify_sum(key: &[u8; 32], voter: &[u8], sum: &Ciphertext, proof: &DleqProof) -> bool {
+    sum_challenge(key, voter, sum, proof).is_some_and(|c| c == proof.c)
+}
+
+fn sum_challenge(key: &[u8; 32], voter: &[u8], sum: &Ciphertext, proof: &DleqProof) -> Option<[u8; 32]> {
+    let shifted = sub(&sum.c2, &BASEPOINT)?;
+    let a = commitment(&proof.z, &BASEPOINT, &proof.c, &sum.c1)?;
+    let b = commitment(&proof.z, key, &proof.c, &shifted)?;
+    Some(challenge(SUM_DOMAIN, voter, &[key, &sum.c1, &sum.c2, &a, &b]))
+}
+
+/// Whether `share` is `s_i·c1` for the secret behind `share_key`, the
+/// trustee's verification key.
+///
+/// The trustee commits `a = w·G`, `b = w·C1` and answers `z = w + c·s_i`,
+/// where `c = H(share, Y_i, C1, D_i, a, b)`.
+pub fn verify_share(share_key: &[u8; 32], c1: &[u8; 32], share: &[u8; 32], proof: &DleqProof) -> bool {
+    share_challenge(share_key, c1, share, proof).is_some_and(|c| c == proof.c)
+}
+
+fn share_challenge(share_key: &[u8; 32], c1: &[u8; 32], share: &[u8; 32], proof: &DleqProof) -> Option<[u8; 32]> {
+    let a = commitment(&proof.z, &BASEPOINT, &proof.c, share_key)?;
+    let b = commitment(&proof.z, c1, &proof.c, share)?;
+    Some(challenge(SHARE_DOMAIN, &[], &[share_key, c1, share, &a, &b]))
+}
+
+/// Whether `ciphertext` decrypts to `count`, given `shares` from exactly
+/// threshold-many trustees as `(index, D_i)` with distinct indices from 1.
+///
+/// The Lagrange coefficients are fractions, so both sides are scaled by the
+/// least common multiple `K` of their denominators: `K·C2 − Σ(K·λ_i)·D_i` must
+/// be `K·count·G`. `K` is below the group order, so this is the same check.
+///
+/// ```
+/// use voting::elgamal::{self, BASEPOINT, IDENTITY};
+/// use voting::Ciphertext;
+///
+/// // Encrypted with no randomness, so every share is the identity.
+/// let two = elgamal::add(&BASEPOINT, &BASEPOINT).unwrap();
+/// let ciphertext = Ciphertext { c1: IDENTITY, c2: two };
+/// assert!(elgamal::verify_tally(&ciphertext, &[(1, IDENTITY), (3, IDENTITY)], 2));
+/// assert!(!elgamal::verify_tally(&ciphertext, &[(1, IDENTITY), (3, IDENTITY)], 1));
+/// ```
+pub fn verify_tally(ciphertext: &Ciphertext, shares: &[(u64, [u8; 32])], count: u64) -> bool {
+    tally_check(ciphertext, shares, count).unwrap_or(false)
+}
+
+fn tally_check(ciphertext: &Ciphertext, shares: &[(u64, [u8; 32])], count: u64) -> Option<bool> {
+    let indices: Vec<u64> = shares.iter().map(|(index, _)| *index).collect();
+    let (scale, weights) = lagrange_weights(&indices)?;
+    let mut plaintext = mul(&scalar(scale), &ciphertext.c2)?;
+    for ((_, share), weight) in shares.iter().zip(weights) {
+        let term = mul(&scalar(weight.unsigned_abs()), share)?;
+        plaintext = if weight < 0 { add(&plaintext, &term)? } else { sub(&plaintext, &term)? };
+    }
+    let expected = mul(&scalar(scale), &mul(&scalar(count as u128), &BASEPOINT)?)?;
+    Some(plaintext == expected)
+}
+
+/// `K` and `K·λ_i` for the Lagrange coefficients at zero,
+/// `λ_i = Π_{j≠i} x_j / (x_j − x_i)`, of the distinct non-zero `indices`.
+fn lagrange_weights(indices: &[u64]) -> Option<(u128, Vec<i128>)> {
+    let fractions = indices
+        .iter()
+        .map(|&i| {
+            indices.iter().filter(|&&j| j != i).try_fold((1i128, 1i128), |(numerator, denominator), &j| {
+                Some((
+                    numerator.checked_mul(j as i128)?,
+                    denominator.checked_mul(j as i128 - i as i128)?,
+                ))
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let scale = fractions
+        .iter()
+        .try_fold(1u128, |scale, (_, denominator)| lcm(scale, denominator.unsigned_abs()))?;
+    let weights = fractions
+        .iter()
+        .map(|(numerator, denominator)| {
+            let factor = i128::try_from(scale / denominator.unsigned_abs()).ok()?;
+            numerator.checked_mul(factor)?.checked_mul(denominator.signum())
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some((scale, weights))
+}
+
+fn lcm(a: u128, b: u128) -> Option<u128> {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    (a / x).checked_mul(b)
+}
@@ -1,149 +1,3726 @@
+// The IDL instructions generated by `#[program]` still call the deprecated
+// `AccountInfo::realloc`. They land in a module at the crate root, next to
+// rather than inside `mod voting`, so the allow cannot be any narrower.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program;
+use anchor_spl::metadata::MetadataAccount;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{transfer_fee::TransferFeeAmount, BaseStateWithExtensions, StateWithExtensions},
+    state::Account as SplTokenAccount,
+};
+use anchor_spl::token_interface::{
+    self, CloseAccount, HarvestWithheldTokensToMint, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+pub mod elgamal;
+pub mod merkle;
+pub mod relay;
 
 declare_id!("7SSMPq4S87sYvyHzhUnLp2v3vr5ZaxQx2vCNBaC4cWaa");
 
+pub const MAX_NAME_LEN: usize = 280;
+pub const MAX_DESCRIPTION_LEN: usize = 280;
+/// Upper bound on candidates in a ranked-choice poll, which also bounds the
+/// number of instant-runoff rounds counted by `count_ranked_ballots`.
+pub const MAX_RANKED_CANDIDATES: u64 = 16;
+/// Longest delegation chain, in hops from a delegator to the delegate who
+/// finally votes.
+pub const MAX_DELEGATION_DEPTH: u8 = 4;
+/// Upper bound on candidates in an encrypted poll, which keeps a ballot and its
+/// proofs within one transaction.
+pub const MAX_ENCRYPTED_CANDIDATES: u64 = 3;
+/// Upper bound on trustees holding shares of an encrypted poll's key.
+pub const MAX_TRUSTEES: usize = 8;
+/// Longest a session key stays valid, in seconds.
+pub const MAX_SESSION_DURATION: i64 = 7 * 24 * 60 * 60;
+/// Upper bound on the polls a session key can be limited to.
+pub const MAX_SESSION_POLLS: usize = 8;
+
 #[program]
 pub mod voting {
     use super::*;
 
-    pub fn initialize_poll(ctx: Context<InitializePoll>, poll_id: u64, description: String, candidates: u64, start_time: u64, end_time: u64) -> Result<()> {
+    /// `start_time` and `end_time` are Unix timestamps in seconds, the same unit
+    /// as `Clock::unix_timestamp`.
+    pub fn initialize_poll(ctx: Context<InitializePoll>, poll_id: u64, description: String, candidates: u64, start_time: u64, end_time: u64, config: PollConfig) -> Result<()> {
         let poll = &mut ctx.accounts.poll;
+        if poll.authority != Pubkey::default() {
+            require_keys_eq!(poll.authority, ctx.accounts.signer.key(), VotingError::Unauthorized);
+            msg!("Poll already initialized, use update_poll to change it");
+            return Ok(());
+        }
+        // Vote records and escrows outlive a closed poll, so a finalized or
+        // cancelled id is never handed out again.
+        require!(ctx.accounts.result.data_is_empty(), VotingError::PollIdFinalized);
+        require!(description.len() <= MAX_DESCRIPTION_LEN, VotingError::DescriptionTooLong);
+        require!(start_time < end_time, VotingError::InvalidPollWindow);
+        validate_poll_config(&config, candidates)?;
+        if let VoteWeight::TokenBalance { mint } = config.vote_weight {
+            let governance_mint = ctx.accounts.governance_mint.as_ref().ok_or(VotingError::MissingGovernanceMint)?;
+            require_keys_eq!(governance_mint.key(), mint, VotingError::GovernanceMintMismatch);
+        }
+
         poll.poll_id = poll_id;
+        poll.authority = ctx.accounts.signer.key();
+        poll.description = description;
+        poll.candidates = candidates;
+        poll.start_time = start_time;
+        poll.end_time = end_time;
+        poll.config = config;
+        poll.status = PollStatus::Draft;
+        poll.registered_candidates = 0;
+        poll.total_votes = 0;
+
+        emit_cpi!(PollCreated {
+            poll_id: poll.poll_id,
+            authority: poll.authority,
+            description: poll.description.clone(),
+            candidates: poll.candidates,
+            start_time: poll.start_time,
+            end_time: poll.end_time,
+            config: poll.config,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_poll(ctx: Context<UpdatePoll>, _poll_id: u64, description: String, candidates: u64, start_time: u64, end_time: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.status == PollStatus::Draft, VotingError::PollAlreadyStarted);
+        require!(description.len() <= MAX_DESCRIPTION_LEN, VotingError::DescriptionTooLong);
+        require!(start_time < end_time, VotingError::InvalidPollWindow);
+        require!(candidates >= poll.registered_candidates, VotingError::CandidateLimitReached);
+        validate_poll_config(&poll.config, candidates)?;
+
         poll.description = description;
         poll.candidates = candidates;
         poll.start_time = start_time;
         poll.end_time = end_time;
 
-        msg!("Poll initialized successfully");
-        msg!("Poll ID: {}", poll.poll_id);
-        msg!("Description: {}", poll.description);
-        msg!("Candidates: {}", poll.candidates);
-        msg!("Start time: {}", poll.start_time);
-        msg!("End time: {}", poll.end_time);
+        emit_cpi!(PollUpdated {
+            poll_id: poll.poll_id,
+            description: poll.description.clone(),
+            candidates: poll.candidates,
+            start_time: poll.start_time,
+            end_time: poll.end_time,
+        });
+
+        Ok(())
+    }
+
+    /// Draft -> Open. Freezes the candidate set and starts accepting votes
+    /// within the poll's time window.
+    pub fn open_poll(ctx: Context<UpdatePoll>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.status == PollStatus::Draft, VotingError::InvalidStatusTransition);
+        require!(poll.registered_candidates > 0, VotingError::NoCandidates);
+        poll.status = PollStatus::Open;
+
+        emit_cpi!(PollStatusChanged {
+            poll_id: poll.poll_id,
+            status: poll.status,
+        });
+
+        Ok(())
+    }
+
+    /// Open -> Closed. Stops accepting votes.
+    pub fn close_poll(ctx: Context<UpdatePoll>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.status == PollStatus::Open, VotingError::InvalidStatusTransition);
+        poll.status = PollStatus::Closed;
+
+        emit_cpi!(PollStatusChanged {
+            poll_id: poll.poll_id,
+            status: poll.status,
+        });
+
+        Ok(())
+    }
+
+    /// Starts the instant-runoff count of a ranked-choice poll once `end_time`
+    /// has passed. Anyone may call it, paying for the poll's `Runoff`. Every
+    /// registered (non-rejected) `Candidate` must be supplied exactly once in
+    /// `remaining_accounts`, as for `finalize_poll`.
+    pub fn start_runoff<'info>(ctx: Context<'_, '_, 'info, 'info, StartRunoff<'info>>, poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.config.voting_method == VotingMethod::RankedChoice, VotingError::WrongVotingMethod);
+        require!(
+            matches!(poll.status, PollStatus::Open | PollStatus::Closed),
+            VotingError::InvalidStatusTransition
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(now as u64 > poll.end_time, VotingError::PollNotEnded);
+
+        let tallies = load_candidate_tallies(ctx.remaining_accounts, poll)?;
+        let runoff = &mut ctx.accounts.runoff;
+        runoff.poll_id = poll_id;
+        runoff.payer = ctx.accounts.payer.key();
+        runoff.remaining = tallies.iter().map(|tally| tally.candidate_id).collect();
+        runoff.votes = vec![0; tallies.len()];
+        // With no ballots the first round is already fully counted.
+        if poll.total_votes == 0 {
+            settle_runoff_round(runoff);
+            emit_cpi!(RunoffRoundCounted {
+                poll_id,
+                round: runoff.rounds.len() as u8,
+                eliminated: None,
+                winners: runoff.winners.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Counts ranked ballots towards the current round of a poll's `Runoff`.
+    /// Their `VoteRecord`s are supplied writable in `remaining_accounts`, as
+    /// many per transaction as fit, and each is counted once per round. The
+    /// call that counts a round's last ballot settles it: a majority or a tie
+    /// decides the poll, otherwise a candidate is eliminated and every ballot
+    /// is counted again in the next round.
+    pub fn count_ranked_ballots<'info>(ctx: Context<'_, '_, 'info, 'info, CountRankedBallots<'info>>, poll_id: u64) -> Result<()> {
+        let total_votes = ctx.accounts.poll.total_votes;
+        let runoff = &mut ctx.accounts.runoff;
+        require!(runoff.winners.is_empty(), VotingError::RunoffDecided);
+
+        let round = runoff.rounds.len() as u8;
+        for account_info in ctx.remaining_accounts.iter() {
+            let mut record = Account::<VoteRecord>::try_from(account_info)?;
+            require!(record.poll_id == poll_id, VotingError::VotePollMismatch);
+            require!(record.rounds_counted == round, VotingError::BallotAlreadyCounted);
+            let Ballot::Ranked { rankings } = &record.ballot else {
+                return err!(VotingError::InvalidBallot);
+            };
+            let preference = rankings
+                .iter()
+                .find_map(|candidate_id| runoff.remaining.iter().position(|remaining| remaining == candidate_id));
+            match preference {
+                Some(index) => runoff.votes[index] = runoff.votes[index].checked_add(1).ok_or(VotingError::Overflow)?,
+                None => runoff.exhausted = runoff.exhausted.checked_add(1).ok_or(VotingError::Overflow)?,
+            }
+            runoff.counted = runoff.counted.checked_add(1).ok_or(VotingError::Overflow)?;
+            record.rounds_counted = round + 1;
+            record.exit(&crate::ID)?;
+        }
+
+        if runoff.counted == total_votes {
+            let eliminated = settle_runoff_round(runoff);
+            emit_cpi!(RunoffRoundCounted {
+                poll_id,
+                round: runoff.rounds.len() as u8,
+                eliminated,
+                winners: runoff.winners.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Open | Closed -> Finalized, once `end_time` has passed. Anyone may call it.
+    ///
+    /// Every registered (non-rejected) `Candidate` of the poll must be supplied
+    /// exactly once in `remaining_accounts`; rejected candidates are skipped. The
+    /// ranked tallies are written to the poll's `PollResult` account. For
+    /// ranked-choice polls the winners and every round are copied from the
+    /// poll's `Runoff`, which must have been decided with `start_runoff` and
+    /// `count_ranked_ballots`. Encrypted polls must have been decrypted with
+    /// `decrypt_tally` first.
+    pub fn finalize_poll<'info>(ctx: Context<'_, '_, 'info, 'info, FinalizePoll<'info>>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            matches!(poll.status, PollStatus::Open | PollStatus::Closed),
+            VotingError::InvalidStatusTransition
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(now as u64 > counting_end_time(poll), VotingError::PollNotEnded);
+        if poll.config.voting_method == VotingMethod::Encrypted {
+            require!(
+                ctx.accounts.encrypted_tally.as_ref().is_some_and(|tally| tally.decrypted),
+                VotingError::TallyNotDecrypted
+            );
+        }
+
+        let mut tallies = load_candidate_tallies(ctx.remaining_accounts, poll)?;
+        let counted = tallies
+            .iter()
+            .try_fold(0u64, |sum, tally| sum.checked_add(tally.votes))
+            .ok_or(VotingError::Overflow)?;
+        require!(counted == poll.total_votes, VotingError::TallyMismatch);
+
+        rank_tallies(&mut tallies);
+        let (rounds, winners) = match poll.config.voting_method {
+            VotingMethod::RankedChoice => {
+                let runoff = ctx.accounts.runoff.as_ref().ok_or(VotingError::RunoffIncomplete)?;
+                require!(!runoff.winners.is_empty(), VotingError::RunoffIncomplete);
+                (runoff.rounds.clone(), runoff.winners.clone())
+            }
+            _ => (Vec::new(), leading_candidates(&tallies)),
+        };
+
+        let result = &mut ctx.accounts.result;
+        result.poll_id = poll.poll_id;
+        result.total_votes = poll.total_votes;
+        // In commit-reveal polls every revealed ballot counts one vote.
+        result.unrevealed = match poll.config.voting_method {
+            VotingMethod::CommitReveal { .. } => poll.commitments.checked_sub(poll.total_votes).ok_or(VotingError::Overflow)?,
+            _ => 0,
+        };
+        result.is_tie = winners.len() > 1;
+        result.winners = winners;
+        result.tallies = tallies;
+        result.rounds = rounds;
+        result.finalized_at = now;
+
+        poll.status = PollStatus::Finalized;
+
+        emit_cpi!(PollFinalized {
+            poll_id: poll.poll_id,
+            total_votes: result.total_votes,
+            winners: result.winners.clone(),
+            is_tie: result.is_tie,
+            unrevealed: result.unrevealed,
+        });
+
+        Ok(())
+    }
+
+    /// Draft | Open -> Cancelled. The poll is abandoned with an empty
+    /// `PollResult` marked `cancelled`, which retires its id and lets its
+    /// accounts be closed.
+    pub fn cancel_poll(ctx: Context<CancelPoll>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            matches!(poll.status, PollStatus::Draft | PollStatus::Open),
+            VotingError::InvalidStatusTransition
+        );
+        poll.status = PollStatus::Cancelled;
+
+        let result = &mut ctx.accounts.result;
+        result.poll_id = poll.poll_id;
+        result.finalized_at = Clock::get()?.unix_timestamp;
+        result.cancelled = true;
+
+        emit_cpi!(PollStatusChanged {
+            poll_id: poll.poll_id,
+            status: poll.status,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_candidate(ctx: Context<InitializeCandidate>, poll_id: u64, name: String, description: String) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.config.candidate_mode == CandidateMode::SelfNomination, VotingError::SelfNominationDisabled);
+
+        let candidate = &mut ctx.accounts.candidate;
+        register_candidate(poll, candidate, ctx.accounts.signer.key(), name, description, CandidateStatus::Pending)?;
+        candidate.payer = ctx.accounts.signer.key();
+
+        emit_cpi!(CandidateRegistered {
+            poll_id,
+            candidate_id: candidate.candidate_id,
+            name: candidate.name.clone(),
+            description: candidate.description.clone(),
+            registered_candidates: poll.registered_candidates,
+        });
+
+        Ok(())
+    }
+
+    /// Registers a candidate on behalf of the poll authority. `candidate_id` is an
+    /// arbitrary identifier chosen by the authority (e.g. an option index encoded
+    /// into 32 bytes) and is used as the candidate PDA seed.
+    pub fn add_candidate(ctx: Context<AddCandidate>, poll_id: u64, candidate_id: Pubkey, name: String, description: String) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.config.candidate_mode == CandidateMode::AuthorityManaged, VotingError::AuthorityManagedDisabled);
+
+        let candidate = &mut ctx.accounts.candidate;
+        register_candidate(poll, candidate, candidate_id, name, description, CandidateStatus::Approved)?;
+        candidate.payer = ctx.accounts.authority.key();
+
+        emit_cpi!(CandidateRegistered {
+            poll_id,
+            candidate_id: candidate.candidate_id,
+            name: candidate.name.clone(),
+            description: candidate.description.clone(),
+            registered_candidates: poll.registered_candidates,
+        });
+
+        Ok(())
+    }
+
+    /// Approves a pending self-nominated candidate so it can receive votes.
+    pub fn approve_candidate(ctx: Context<ModerateCandidate>, poll_id: u64, _candidate_id: Pubkey) -> Result<()> {
+        let candidate = &mut ctx.accounts.candidate;
+        candidate.status = CandidateStatus::Approved;
+
+        emit_cpi!(CandidateStatusChanged {
+            poll_id,
+            candidate_id: candidate.candidate_id,
+            status: candidate.status,
+        });
+
+        Ok(())
+    }
+
+    /// Rejects a pending self-nominated candidate and frees its slot.
+    pub fn reject_candidate(ctx: Context<ModerateCandidate>, poll_id: u64, _candidate_id: Pubkey) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        poll.registered_candidates = poll.registered_candidates.checked_sub(1).ok_or(VotingError::Overflow)?;
+
+        let candidate = &mut ctx.accounts.candidate;
+        candidate.status = CandidateStatus::Rejected;
+
+        emit_cpi!(CandidateStatusChanged {
+            poll_id,
+            candidate_id: candidate.candidate_id,
+            status: candidate.status,
+        });
+
+        Ok(())
+    }
+
+    /// `proof` is required in allowlist polls and ignored otherwise.
+    ///
+    /// Approval and quadratic polls take the rest of the ballot in `choices`,
+    /// whose first entry must be `candidate`. The other entries' `Candidate`
+    /// accounts must be supplied writable and in the same order in
+    /// `remaining_accounts`, and a quadratic ballot also creates the voter's
+    /// `credits`: `n` votes for one candidate cost `n²` of the poll's
+    /// `credit_budget`.
+    ///
+    /// The signer may be a session key voting for its owner, with its
+    /// `SessionKey` supplied as `session`; the ballot is then the owner's in
+    /// every respect, except that the session key pays the rent.
+    ///
+    /// In single-choice polls a delegate also casts the weight of its
+    /// delegators, supplied in `remaining_accounts` (see `count_delegators`).
+    /// If the signer's own weight was already cast by a delegate, this vote
+    /// overrides it: `delegate_vote` and, unless it is `candidate`,
+    /// `delegate_candidate` must be supplied so the weight can be taken back.
+    pub fn vote<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Vote<'info>>,
+        poll_id: u64,
+        candidate_id: Pubkey,
+        proof: Option<EligibilityProof>,
+        choices: Option<BallotChoices>,
+    ) -> Result<()> {
+        require_voting_open(&ctx.accounts.poll)?;
+        match (ctx.accounts.poll.config.voting_method, &choices) {
+            (VotingMethod::SingleChoice, None) => {}
+            (VotingMethod::Approval { max_selections }, Some(BallotChoices::Approval { selections })) => {
+                require!(selections.first() == Some(&candidate_id), VotingError::InvalidBallot);
+                require!(selections.len() <= max_selections as usize, VotingError::TooManySelections);
+            }
+            (VotingMethod::Quadratic { .. }, Some(BallotChoices::Quadratic { allocations })) => {
+                require!(
+                    allocations.first().map(|allocation| allocation.candidate_id) == Some(candidate_id),
+                    VotingError::InvalidBallot
+                );
+                require!(allocations.iter().all(|allocation| allocation.votes > 0), VotingError::InvalidBallot);
+            }
+            _ => return err!(VotingError::WrongVotingMethod),
+        }
+        if let Some(session) = &ctx.accounts.session {
+            require_session(session, poll_id)?;
+        }
+        let voter = voter_key(&ctx.accounts.signer, ctx.accounts.session.as_ref());
+
+        let mut snapshot_weight = None;
+        let nft_mint = match ctx.accounts.poll.config.eligibility {
+            Eligibility::Open => None,
+            Eligibility::Allowlist { root } => {
+                let proof = proof.ok_or(VotingError::MissingEligibilityProof)?;
+                let leaf = merkle::leaf(&voter, proof.weight);
+                require!(merkle::verify(&root, leaf, &proof.proof), VotingError::NotEligible);
+                snapshot_weight = Some(proof.weight);
+                None
+            }
+            Eligibility::NftCollection { collection } => Some(require_collection_nft(
+                &voter,
+                &collection,
+                ctx.accounts.nft_token_account.as_ref(),
+                ctx.accounts.nft_metadata.as_ref(),
+            )?),
+        };
+
+        let end_time = ctx.accounts.poll.end_time;
+        let weight = match ctx.accounts.poll.config.vote_weight {
+            VoteWeight::OnePerVoter => 1,
+            VoteWeight::Snapshot => {
+                let weight = snapshot_weight.ok_or(VotingError::InvalidPollConfig)?;
+                require!(weight > 0, VotingError::NoVotingWeight);
+                weight
+            }
+            VoteWeight::TokenBalance { mint } => {
+                let escrow = ctx.accounts.escrow.as_mut().ok_or(VotingError::MissingEscrow)?;
+                require_keys_eq!(escrow.mint, mint, VotingError::GovernanceMintMismatch);
+                require!(escrow.amount > 0, VotingError::NoVotingWeight);
+                escrow.locked_weight = escrow.amount;
+                escrow.locked_until = end_time;
+                escrow.amount
+            }
+        };
+
+        if !ctx.accounts.delegated_vote.data_is_empty() {
+            let marker = DelegatedVote::try_deserialize(&mut &ctx.accounts.delegated_vote.try_borrow_data()?[..])?;
+            override_delegated_vote(ctx.accounts, poll_id, candidate_id, &marker)?;
+            let marker_payer = ctx.accounts.delegated_vote_payer.as_ref().ok_or(VotingError::PayerMismatch)?;
+            require_keys_eq!(marker_payer.key(), marker.payer, VotingError::PayerMismatch);
+            close_marker(&ctx.accounts.delegated_vote, marker_payer)?;
+            emit_cpi!(DelegationOverridden {
+                poll_id,
+                delegator: marker.delegator,
+                delegate: marker.delegate,
+                weight: marker.weight,
+            });
+        }
+
+        // The remaining accounts of an approval or quadratic ballot are its candidates.
+        let delegated_weight = if choices.is_some() || ctx.remaining_accounts.is_empty() {
+            0
+        } else {
+            let poll = &ctx.accounts.poll;
+            require!(
+                poll.config.eligibility == Eligibility::Open && poll.config.vote_weight != VoteWeight::Snapshot,
+                VotingError::DelegationUnsupported
+            );
+            count_delegators(
+                ctx.remaining_accounts,
+                poll,
+                &voter,
+                &ctx.accounts.signer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+            )?
+        };
+        let weight = weight.checked_add(delegated_weight).ok_or(VotingError::Overflow)?;
+
+        let vote_record = &mut ctx.accounts.vote;
+        vote_record.voter = voter;
+        vote_record.payer = ctx.accounts.signer.key();
+        vote_record.poll_id = poll_id;
+        vote_record.candidate = candidate_id;
+        vote_record.weight = weight;
+        vote_record.delegated_weight = delegated_weight;
+        vote_record.nft_mint = nft_mint;
+
+        let candidate = &mut ctx.accounts.candidate;
+        let poll = &mut ctx.accounts.poll;
+        match choices {
+            None => {
+                vote_record.ballot = Ballot::Single;
+                candidate.votes = candidate.votes.checked_add(weight).ok_or(VotingError::Overflow)?;
+                poll.total_votes = poll.total_votes.checked_add(weight).ok_or(VotingError::Overflow)?;
+
+                emit_cpi!(VoteCast {
+                    poll_id: vote_record.poll_id,
+                    voter: vote_record.voter,
+                    candidate: vote_record.candidate,
+                    weight,
+                    delegated_weight,
+                    candidate_votes: candidate.votes,
+                    total_votes: poll.total_votes,
+                });
+            }
+            Some(BallotChoices::Approval { selections }) => {
+                candidate.votes = candidate.votes.checked_add(1).ok_or(VotingError::Overflow)?;
+                for mut other in load_other_ballot_candidates(ctx.remaining_accounts, poll_id, &selections)? {
+                    other.votes = other.votes.checked_add(1).ok_or(VotingError::Overflow)?;
+                    other.exit(&crate::ID)?;
+                }
+                poll.total_votes = poll
+                    .total_votes
+                    .checked_add(selections.len() as u64)
+                    .ok_or(VotingError::Overflow)?;
+                vote_record.ballot = Ballot::Approval {
+                    selections: selections.clone(),
+                };
+
+                emit_cpi!(ApprovalVoteCast {
+                    poll_id,
+                    voter,
+                    selections,
+                    total_votes: poll.total_votes,
+                });
+            }
+            Some(BallotChoices::Quadratic { allocations }) => {
+                let VotingMethod::Quadratic { credit_budget } = poll.config.voting_method else {
+                    return err!(VotingError::WrongVotingMethod);
+                };
+                let mut credits_spent = 0u64;
+                let mut votes_cast = 0u64;
+                for allocation in allocations.iter() {
+                    let cost = allocation.votes.checked_mul(allocation.votes).ok_or(VotingError::Overflow)?;
+                    credits_spent = credits_spent.checked_add(cost).ok_or(VotingError::Overflow)?;
+                    votes_cast = votes_cast.checked_add(allocation.votes).ok_or(VotingError::Overflow)?;
+                }
+                require!(credits_spent <= credit_budget, VotingError::InsufficientCredits);
+
+                let candidate_ids: Vec<Pubkey> = allocations.iter().map(|allocation| allocation.candidate_id).collect();
+                let others = load_other_ballot_candidates(ctx.remaining_accounts, poll_id, &candidate_ids)?;
+                candidate.votes = candidate.votes.checked_add(allocations[0].votes).ok_or(VotingError::Overflow)?;
+                for (mut other, allocation) in others.into_iter().zip(allocations[1..].iter()) {
+                    other.votes = other.votes.checked_add(allocation.votes).ok_or(VotingError::Overflow)?;
+                    other.exit(&crate::ID)?;
+                }
+                poll.total_votes = poll.total_votes.checked_add(votes_cast).ok_or(VotingError::Overflow)?;
+
+                let credits = ctx.accounts.credits.as_mut().ok_or(VotingError::MissingCredits)?;
+                credits.poll_id = poll_id;
+                credits.voter = voter;
+                credits.budget = credit_budget;
+                credits.spent = credits_spent;
+                vote_record.ballot = Ballot::Quadratic {
+                    allocations: allocations.clone(),
+                };
+
+                emit_cpi!(QuadraticVoteCast {
+                    poll_id,
+                    voter,
+                    allocations,
+                    credits_spent,
+                    total_votes: poll.total_votes,
+                });
+            }
+        }
 
         Ok(())
     }
 
-    pub fn initialize_candidate(ctx: Context<InitializeCandidate>, _poll_id: u64, name: String, description: String) -> Result<()> {
+    /// Casts a single-choice ballot for `voter`, authorized by their ed25519
+    /// signature over `relay::message(poll_id, candidate_id, nonce)` instead of
+    /// a transaction signature, so that the signing relayer pays the fees and
+    /// rent. The Ed25519 program instruction verifying the signature must come
+    /// immediately before this one, and `nonce` must be the voter's next nonce.
+    /// Only polls open to everyone with one vote per voter accept relayed ballots.
+    pub fn vote_with_signature(
+        ctx: Context<VoteWithSignature>,
+        poll_id: u64,
+        candidate_id: Pubkey,
+        voter: Pubkey,
+        nonce: u64,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require_voting_open(poll)?;
+        require!(poll.config.voting_method == VotingMethod::SingleChoice, VotingError::WrongVotingMethod);
+        require!(
+            poll.config.vote_weight == VoteWeight::OnePerVoter && poll.config.eligibility == Eligibility::Open,
+            VotingError::RelayUnsupported
+        );
+        // Taking the weight back from a delegate is left to the voter's own `vote`.
+        require!(ctx.accounts.delegated_vote.data_is_empty(), VotingError::DelegatedVoteCast);
+
+        relay::verify_signature(&ctx.accounts.instructions, &voter, &relay::message(poll_id, &candidate_id, nonce))?;
+        let voter_nonce = &mut ctx.accounts.voter_nonce;
+        require!(nonce == voter_nonce.nonce, VotingError::InvalidNonce);
+        if voter_nonce.payer == Pubkey::default() {
+            voter_nonce.payer = ctx.accounts.relayer.key();
+        }
+        voter_nonce.poll_id = poll_id;
+        voter_nonce.voter = voter;
+        voter_nonce.nonce = nonce.checked_add(1).ok_or(VotingError::Overflow)?;
+
+        let vote_record = &mut ctx.accounts.vote;
+        vote_record.voter = voter;
+        vote_record.payer = ctx.accounts.relayer.key();
+        vote_record.poll_id = poll_id;
+        vote_record.candidate = candidate_id;
+        vote_record.weight = 1;
+        vote_record.ballot = Ballot::Single;
+
         let candidate = &mut ctx.accounts.candidate;
-        candidate.candidate_id = ctx.accounts.signer.key();
-        candidate.name = name;
-        candidate.description = description;
+        candidate.votes = candidate.votes.checked_add(1).ok_or(VotingError::Overflow)?;
+        poll.total_votes = poll.total_votes.checked_add(1).ok_or(VotingError::Overflow)?;
+
+        emit_cpi!(VoteCast {
+            poll_id,
+            voter,
+            candidate: candidate_id,
+            weight: 1,
+            delegated_weight: 0,
+            candidate_votes: candidate.votes,
+            total_votes: poll.total_votes,
+        });
+
+        Ok(())
+    }
+
+    /// Authorizes `session_key` to cast `vote`s for the signer until
+    /// `expires_at`, a Unix timestamp in seconds at most
+    /// `MAX_SESSION_DURATION` away, in the listed `polls` or in any poll if
+    /// none are listed. The session key co-signs, so nobody else can claim its
+    /// session address first.
+    pub fn create_session(ctx: Context<CreateSession>, expires_at: i64, polls: Vec<u64>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            expires_at > now && expires_at - now <= MAX_SESSION_DURATION,
+            VotingError::InvalidSessionExpiry
+        );
+        require!(polls.len() <= MAX_SESSION_POLLS, VotingError::TooManySessionPolls);
+
+        let session = &mut ctx.accounts.session;
+        session.owner = ctx.accounts.owner.key();
+        session.session_key = ctx.accounts.session_key.key();
+        session.expires_at = expires_at;
+        session.polls = polls;
+
+        emit_cpi!(SessionCreated {
+            owner: session.owner,
+            session_key: session.session_key,
+            expires_at,
+            polls: session.polls.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Revokes a session key, expired or not, and returns its rent to the owner.
+    pub fn revoke_session(ctx: Context<RevokeSession>, session_key: Pubkey) -> Result<()> {
+        emit_cpi!(SessionRevoked {
+            owner: ctx.accounts.owner.key(),
+            session_key,
+        });
+
+        Ok(())
+    }
+
+    /// Delegates the signer's vote to `delegate`: in every poll when `poll_id`
+    /// is `None`, or only in that poll. A per-poll delegation takes precedence
+    /// over a global one, and calling this again re-points the delegation.
+    ///
+    /// `remaining_accounts` walk the delegate's existing chain so that cycles
+    /// and chains longer than `MAX_DELEGATION_DEPTH` are rejected: for each
+    /// hop, its per-poll delegation PDA (per-poll delegations only) and then
+    /// its global delegation PDA, up to the first hop with neither.
+    ///
+    /// A global delegation cannot see every poll's per-poll delegations, so it
+    /// may still close a loop through one of them. Such a loop never counts
+    /// anyone twice: `vote` rejects a delegator that leads back to the voter.
+    pub fn delegate_vote(ctx: Context<DelegateVote>, poll_id: Option<u64>, delegate: Pubkey) -> Result<()> {
+        let delegator = ctx.accounts.signer.key();
+        require_acyclic_chain(ctx.remaining_accounts, poll_id, &delegator, &delegate)?;
+
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.delegator = delegator;
+        delegation.delegate = delegate;
+        delegation.poll_id = poll_id;
 
-        msg!("Candidate initialized successfully");
-        msg!("Candidate ID: {}", candidate.candidate_id);
-        msg!("Name: {}", candidate.name);
-        msg!("Description: {}", candidate.description);
+        emit_cpi!(DelegationSet { delegator, delegate, poll_id });
 
         Ok(())
     }
 
-    pub fn vote(ctx: Context<Vote>, poll_id: u64, candidate_id: Pubkey) -> Result<()> {
+    /// Removes a delegation and returns its rent. Weight a delegate has
+    /// already cast stays counted until the delegator votes directly.
+    pub fn undelegate_vote(ctx: Context<UndelegateVote>, poll_id: Option<u64>) -> Result<()> {
+        emit_cpi!(DelegationRemoved {
+            delegator: ctx.accounts.signer.key(),
+            delegate: ctx.accounts.delegation.delegate,
+            poll_id,
+        });
+
+        Ok(())
+    }
+
+    /// Casts a ranked-choice ballot. `rankings` lists candidate ids from most to
+    /// least preferred, and the matching `Candidate` accounts must be supplied in
+    /// the same order in `remaining_accounts`. The first preference is counted
+    /// in `Candidate.votes`; the full ranking is kept in the `VoteRecord` for
+    /// the runoff count.
+    pub fn vote_ranked<'info>(ctx: Context<'_, '_, 'info, 'info, VoteRanked<'info>>, poll_id: u64, rankings: Vec<Pubkey>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require_voting_open(poll)?;
+        require!(poll.config.voting_method == VotingMethod::RankedChoice, VotingError::WrongVotingMethod);
+        require!(
+            !rankings.is_empty() && rankings.len() as u64 <= poll.registered_candidates,
+            VotingError::InvalidBallot
+        );
+
+        let mut candidates = load_ballot_candidates(ctx.remaining_accounts, poll_id, &rankings)?;
+        let first_preference = &mut candidates[0];
+        first_preference.votes = first_preference.votes.checked_add(1).ok_or(VotingError::Overflow)?;
+        first_preference.exit(&crate::ID)?;
+        poll.total_votes = poll.total_votes.checked_add(1).ok_or(VotingError::Overflow)?;
+
+        let vote_record = &mut ctx.accounts.vote;
+        vote_record.voter = ctx.accounts.signer.key();
+        vote_record.payer = ctx.accounts.signer.key();
+        vote_record.poll_id = poll_id;
+        vote_record.candidate = rankings[0];
+        vote_record.weight = 1;
+        vote_record.ballot = Ballot::Ranked {
+            rankings: rankings.clone(),
+        };
+
+        emit_cpi!(RankedVoteCast {
+            poll_id,
+            voter: vote_record.voter,
+            rankings,
+            total_votes: poll.total_votes,
+        });
+
+        Ok(())
+    }
+
+    /// Submits a sealed ballot in a commit-reveal poll: `commitment` is
+    /// `sha256(poll_id || voter || candidate_id || salt)`, with `poll_id` as
+    /// 8 little-endian bytes and a 32-byte `salt` kept by the voter. Binding the
+    /// poll and voter stops a commitment being copied and revealed by another.
+    pub fn commit_vote(ctx: Context<CommitVote>, poll_id: u64, commitment: [u8; 32]) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require_voting_open(poll)?;
+        require!(
+            matches!(poll.config.voting_method, VotingMethod::CommitReveal { .. }),
+            VotingError::WrongVotingMethod
+        );
+        poll.commitments = poll.commitments.checked_add(1).ok_or(VotingError::Overflow)?;
+
+        let vote_commitment = &mut ctx.accounts.commitment;
+        vote_commitment.poll_id = poll_id;
+        vote_commitment.voter = ctx.accounts.signer.key();
+        vote_commitment.commitment = commitment;
+        vote_commitment.revealed = false;
+
+        emit_cpi!(VoteCommitted {
+            poll_id,
+            voter: vote_commitment.voter,
+            commitment,
+            commitments: poll.commitments,
+        });
+
+        Ok(())
+    }
+
+    /// Opens a sealed ballot during the reveal window, which follows
+    /// `end_time` for `reveal_duration` seconds, and counts it.
+    pub fn reveal_vote(ctx: Context<RevealVote>, poll_id: u64, candidate_id: Pubkey, salt: [u8; 32]) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            matches!(poll.status, PollStatus::Open | PollStatus::Closed),
+            VotingError::RevealNotOpen
+        );
+        let now = Clock::get()?.unix_timestamp as u64;
+        require!(now > poll.end_time && now <= counting_end_time(poll), VotingError::RevealNotOpen);
+
+        let vote_commitment = &mut ctx.accounts.commitment;
+        let expected = commitment_hash(poll_id, &vote_commitment.voter, &candidate_id, &salt);
+        require!(vote_commitment.commitment == expected, VotingError::CommitmentMismatch);
+        vote_commitment.revealed = true;
+
         let vote_record = &mut ctx.accounts.vote;
         vote_record.voter = ctx.accounts.signer.key();
+        vote_record.payer = ctx.accounts.signer.key();
         vote_record.poll_id = poll_id;
         vote_record.candidate = candidate_id;
+        vote_record.weight = 1;
+        vote_record.ballot = Ballot::Single;
+
+        let candidate = &mut ctx.accounts.candidate;
+        candidate.votes = candidate.votes.checked_add(1).ok_or(VotingError::Overflow)?;
+        poll.total_votes = poll.total_votes.checked_add(1).ok_or(VotingError::Overflow)?;
 
-        msg!("Vote recorded successfully");
-        msg!("Voter: {}", vote_record.voter);
-        msg!("Poll ID: {}", vote_record.poll_id);
-        msg!("Candidate: {}", vote_record.candidate);
+        emit_cpi!(VoteRevealed {
+            poll_id,
+            voter: vote_record.voter,
+            candidate: candidate_id,
+            candidate_votes: candidate.votes,
+            total_votes: poll.total_votes,
+        });
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(_poll_id: u64)]
-pub struct InitializePoll<'info> {
-    #[account(mut)]
-    pub signer: Signer<'info>,
-    #[account(
-        init_if_needed,
-        payer = signer,
-        space = 8 + Poll::INIT_SPACE,
-        seeds = [b"poll".as_ref(), _poll_id.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub poll: Account<'info, Poll>,
-    pub system_program: Program<'info, System>,
-}
+    /// Sets up the tally of an encrypted poll once its candidates are final.
+    /// Each of `trustees` then registers its part of the poll key with
+    /// `register_trustee_key`, and any `threshold` of them can later decrypt the
+    /// tally. Every registered candidate must be supplied in
+    /// `remaining_accounts`; their order is the order of a ballot's ciphertexts.
+    pub fn configure_encrypted_tally<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ConfigureEncryptedTally<'info>>,
+        poll_id: u64,
+        trustees: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.status == PollStatus::Draft, VotingError::PollNotDraft);
+        require!(poll.config.voting_method == VotingMethod::Encrypted, VotingError::WrongVotingMethod);
+        require!(poll.registered_candidates > 0, VotingError::NoCandidates);
+        require!(!trustees.is_empty() && trustees.len() <= MAX_TRUSTEES, VotingError::InvalidPollConfig);
+        require!(threshold > 0 && threshold as usize <= trustees.len(), VotingError::InvalidPollConfig);
+        for (index, trustee) in trustees.iter().enumerate() {
+            require!(!trustees[..index].contains(trustee), VotingError::InvalidPollConfig);
+        }
 
-#[derive(Accounts)]
-#[instruction(poll_id: u64)]
-pub struct InitializeCandidate<'info> {
-    #[account(mut)]
-    pub signer: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub poll: Account<'info, Poll>,
-    #[account(
-        init,
-        payer = signer,
-        space = 8 + Candidate::INIT_SPACE,
-        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
-        bump
-    )]
-    pub candidate: Account<'info, Candidate>,
-    pub system_program: Program<'info, System>,
-}
+        let mut candidate_ids = Vec::with_capacity(poll.registered_candidates as usize);
+        for account_info in ctx.remaining_accounts.iter() {
+            let candidate = Account::<Candidate>::try_from(account_info)?;
+            require!(candidate.poll_id == poll_id, VotingError::CandidatePollMismatch);
+            require!(candidate.status == CandidateStatus::Approved, VotingError::CandidateNotApproved);
+            require!(!candidate_ids.contains(&candidate.candidate_id), VotingError::DuplicateCandidate);
+            candidate_ids.push(candidate.candidate_id);
+        }
+        require!(candidate_ids.len() as u64 == poll.registered_candidates, VotingError::IncompleteCandidates);
 
-#[derive(Accounts)]
-#[instruction(poll_id: u64, candidate_id: Pubkey)]
-pub struct Vote<'info> {
-    #[account(mut)]
-    pub signer: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub poll: Account<'info, Poll>,
-    #[account(
-        mut,
-        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), candidate_id.as_ref()],
-        bump
-    )]
-    pub candidate: Account<'info, Candidate>,
-    #[account(
-        init,
-        payer = signer,
-        space = 8 + VoteRecord::INIT_SPACE,
-        seeds = [b"vote".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
-        bump
-    )]
-    pub vote: Account<'info, VoteRecord>,
-    pub system_program: Program<'info, System>,
-}
+        let tally = &mut ctx.accounts.encrypted_tally;
+        tally.poll_id = poll_id;
+        tally.payer = ctx.accounts.authority.key();
+        tally.public_key = elgamal::IDENTITY;
+        tally.threshold = threshold;
+        tally.trustees = trustees
+            .iter()
+            .map(|authority| Trustee {
+                authority: *authority,
+                registered: false,
+                shares: Vec::new(),
+            })
+            .collect();
+        tally.commitments = vec![elgamal::IDENTITY; threshold as usize];
+        tally.ciphertexts = vec![
+            Ciphertext {
+                c1: elgamal::IDENTITY,
+                c2: elgamal::IDENTITY,
+            };
+            candidate_ids.len()
+        ];
+        tally.candidate_ids = candidate_ids;
+        tally.ballots = 0;
+        tally.decrypted = false;
 
-#[account]
-#[derive(InitSpace)]
-pub struct Poll {
-    pub poll_id: u64,
-    #[max_len(280)]
-    pub description: String,
-    pub candidates: u64,
-    pub start_time: u64,
-    pub end_time: u64,
-}
+        emit_cpi!(EncryptedTallyConfigured {
+            poll_id,
+            trustees,
+            threshold,
+            candidate_ids: tally.candidate_ids.clone(),
+        });
 
-#[account]
-#[derive(InitSpace)]
-pub struct Candidate {
-    pub candidate_id: Pubkey,
-    #[max_len(280)]
-    pub name: String,
-    #[max_len(280)]
-    pub description: String,
-}
+        Ok(())
+    }
 
-#[account]
-#[derive(InitSpace)]
-pub struct VoteRecord {
-    pub voter: Pubkey,
-    pub poll_id: u64,
-    pub candidate: Pubkey,
+    /// Registers the signing trustee's commitments `C_k = a_k·G` to the
+    /// `threshold` coefficients of its secret polynomial, with a proof of
+    /// knowledge of `a_0` so that no trustee can choose its key to cancel out
+    /// the others'. Accepted only while the poll is in draft; see [`elgamal`]
+    /// for how trustees derive their shares.
+    pub fn register_trustee_key(
+        ctx: Context<RegisterTrusteeKey>,
+        poll_id: u64,
+        commitments: Vec<[u8; 32]>,
+        proof: SchnorrProof,
+    ) -> Result<()> {
+        require!(ctx.accounts.poll.status == PollStatus::Draft, VotingError::PollNotDraft);
+
+        let tally = &mut *ctx.accounts.encrypted_tally;
+        let trustee = ctx.accounts.trustee.key();
+        let index = tally
+            .trustees
+            .iter()
+            .position(|candidate| candidate.authority == trustee)
+            .ok_or(VotingError::NotTrustee)?;
+        require!(!tally.trustees[index].registered, VotingError::TrusteeRegistered);
+        require!(commitments.len() == tally.threshold as usize, VotingError::InvalidTrusteeKey);
+        let context = [trustee.as_ref(), &poll_id.to_le_bytes()].concat();
+        require!(elgamal::verify_key(&context, &commitments[0], &proof), VotingError::InvalidTrusteeKey);
+
+        for (sum, commitment) in tally.commitments.iter_mut().zip(commitments.iter()) {
+            *sum = elgamal::add(sum, commitment).ok_or(VotingError::InvalidTrusteeKey)?;
+        }
+        tally.public_key = tally.commitments[0];
+        tally.trustees[index].registered = true;
+
+        emit_cpi!(TrusteeKeyRegistered {
+            poll_id,
+            trustee,
+            commitments,
+            public_key: tally.public_key,
+            remaining: tally.trustees.iter().filter(|trustee| !trustee.registered).count() as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Casts an encrypted ballot: one ciphertext per candidate, in the tally's
+    /// candidate order, each proven to encrypt 0 or 1, with a proof that they
+    /// sum to exactly 1. The ciphertexts are added to the poll's tally; no
+    /// candidate tally changes until `decrypt_tally`.
+    pub fn vote_encrypted(ctx: Context<VoteEncrypted>, poll_id: u64, ballot: EncryptedBallot) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require_voting_open(poll)?;
+        require!(poll.config.voting_method == VotingMethod::Encrypted, VotingError::WrongVotingMethod);
+
+        let tally = &mut *ctx.accounts.encrypted_tally;
+        require!(tally.trustees.iter().all(|trustee| trustee.registered), VotingError::TrusteeKeysIncomplete);
+        // Candidates registered after the tally was configured have no ciphertext.
+        require!(tally.candidate_ids.len() as u64 == poll.registered_candidates, VotingError::IncompleteCandidates);
+        require!(ballot.choices.len() == tally.ciphertexts.len(), VotingError::InvalidBallot);
+
+        let voter = ctx.accounts.signer.key();
+        let mut sum = Ciphertext {
+            c1: elgamal::IDENTITY,
+            c2: elgamal::IDENTITY,
+        };
+        for (choice, total) in ballot.choices.iter().zip(tally.ciphertexts.iter_mut()) {
+            require!(
+                elgamal::verify_bit(&tally.public_key, voter.as_ref(), &choice.ciphertext, &choice.proof),
+                VotingError::InvalidBallotProof
+            );
+            sum = elgamal::add_ciphertexts(&sum, &choice.ciphertext).ok_or(VotingError::InvalidBallotProof)?;
+            *total = elgamal::add_ciphertexts(total, &choice.ciphertext).ok_or(VotingError::InvalidBallotProof)?;
+        }
+        require!(
+            elgamal::verify_sum(&tally.public_key, voter.as_ref(), &sum, &ballot.sum_proof),
+            VotingError::InvalidBallotProof
+        );
+        tally.ballots = tally.ballots.checked_add(1).ok_or(VotingError::Overflow)?;
+
+        let vote_record = &mut ctx.accounts.vote;
+        vote_record.voter = voter;
+        vote_record.payer = ctx.accounts.signer.key();
+        vote_record.poll_id = poll_id;
+        vote_record.candidate = Pubkey::default();
+        vote_record.weight = 1;
+        vote_record.ballot = Ballot::Encrypted;
+
+        emit_cpi!(EncryptedVoteCast {
+            poll_id,
+            voter,
+            ciphertexts: ballot.choices.iter().map(|choice| choice.ciphertext).collect(),
+            ballots: tally.ballots,
+        });
+
+        Ok(())
+    }
+
+    /// Posts the signing trustee's decryption share for every candidate's
+    /// ciphertext, each with a proof that it matches the trustee's verification
+    /// key. Only accepted after `end_time`, once the tally can no longer change.
+    pub fn post_decryption_share(ctx: Context<PostDecryptionShare>, _poll_id: u64, shares: Vec<DecryptionShare>) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(
+            matches!(poll.status, PollStatus::Open | PollStatus::Closed),
+            VotingError::InvalidStatusTransition
+        );
+        let now = Clock::get()?.unix_timestamp as u64;
+        require!(now > poll.end_time, VotingError::PollNotEnded);
+
+        let tally = &mut *ctx.accounts.encrypted_tally;
+        require!(tally.trustees.iter().all(|trustee| trustee.registered), VotingError::TrusteeKeysIncomplete);
+        let trustee = ctx.accounts.trustee.key();
+        let index = tally
+            .trustees
+            .iter()
+            .position(|candidate| candidate.authority == trustee)
+            .ok_or(VotingError::NotTrustee)?;
+        require!(!tally.trustees[index].posted(), VotingError::SharePosted);
+        require!(shares.len() == tally.ciphertexts.len(), VotingError::InvalidDecryptionShare);
+
+        let verification_key =
+            elgamal::verification_key(&tally.commitments, index as u64 + 1).ok_or(VotingError::InvalidTrusteeKey)?;
+        for (share, ciphertext) in shares.iter().zip(tally.ciphertexts.iter()) {
+            require!(
+                elgamal::verify_share(&verification_key, &ciphertext.c1, &share.share, &share.proof),
+                VotingError::InvalidDecryptionShare
+            );
+        }
+        tally.trustees[index].shares = shares.iter().map(|share| share.share).collect();
+
+        let posted = tally.trustees.iter().filter(|trustee| trustee.posted()).count();
+        emit_cpi!(DecryptionSharePosted {
+            poll_id: tally.poll_id,
+            trustee,
+            shares: tally.trustees[index].shares.clone(),
+            remaining: (tally.threshold as usize).saturating_sub(posted) as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Publishes the decrypted tally of an encrypted poll once `threshold`
+    /// trustees have posted shares; the first `threshold` of them, in trustee
+    /// order, are combined. `counts` are the votes per candidate, in the tally's
+    /// candidate order, recovered off-chain from `C2 − Σλ_i·D_i = count·G`; each
+    /// is checked before being written to the `Candidate` accounts, supplied
+    /// writable and in the same order in `remaining_accounts`. Anyone may call it.
+    pub fn decrypt_tally<'info>(ctx: Context<'_, '_, 'info, 'info, DecryptTally<'info>>, poll_id: u64, counts: Vec<u64>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            matches!(poll.status, PollStatus::Open | PollStatus::Closed),
+            VotingError::InvalidStatusTransition
+        );
+        let tally = &mut ctx.accounts.encrypted_tally;
+        let decrypting: Vec<(u64, &Trustee)> = tally
+            .trustees
+            .iter()
+            .enumerate()
+            .filter(|(_, trustee)| trustee.posted())
+            .take(tally.threshold as usize)
+            .map(|(index, trustee)| (index as u64 + 1, trustee))
+            .collect();
+        require!(decrypting.len() == tally.threshold as usize, VotingError::DecryptionIncomplete);
+        require!(counts.len() == tally.ciphertexts.len(), VotingError::InvalidTally);
+        for (candidate, (ciphertext, count)) in tally.ciphertexts.iter().zip(counts.iter()).enumerate() {
+            let shares: Vec<(u64, [u8; 32])> = decrypting
+                .iter()
+                .map(|(index, trustee)| (*index, trustee.shares[candidate]))
+                .collect();
+            require!(elgamal::verify_tally(ciphertext, &shares, *count), VotingError::InvalidTally);
+        }
+        let counted = counts
+            .iter()
+            .try_fold(0u64, |sum, count| sum.checked_add(*count))
+            .ok_or(VotingError::Overflow)?;
+        require!(counted == tally.ballots, VotingError::TallyMismatch);
+
+        for (candidate, count) in load_ballot_candidates(ctx.remaining_accounts, poll_id, &tally.candidate_ids)?
+            .iter_mut()
+            .zip(counts.iter())
+        {
+            candidate.votes = *count;
+            candidate.exit(&crate::ID)?;
+        }
+        poll.total_votes = tally.ballots;
+        tally.decrypted = true;
+
+        emit_cpi!(EncryptedTallyDecrypted {
+            poll_id,
+            counts,
+            total_votes: poll.total_votes,
+        });
+
+        Ok(())
+    }
+
+    /// Locks governance tokens of a token-weighted poll into the voter's vault.
+    /// The escrowed amount is the weight of the voter's next `vote`.
+    pub fn deposit_tokens(ctx: Context<DepositTokens>, poll_id: u64, amount: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        let VoteWeight::TokenBalance { mint } = poll.config.vote_weight else {
+            return err!(VotingError::WrongVotingMethod);
+        };
+        require_keys_eq!(ctx.accounts.mint.key(), mint, VotingError::GovernanceMintMismatch);
+        require!(
+            matches!(poll.status, PollStatus::Draft | PollStatus::Open),
+            VotingError::InvalidStatusTransition
+        );
+        require!(amount > 0, VotingError::NoVotingWeight);
+
+        require!(ctx.accounts.escrow.locked_weight == 0, VotingError::TokensLocked);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.voter_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        // Token-2022 transfer fees are withheld from what reaches the vault.
+        let balance = ctx.accounts.vault.amount;
+        ctx.accounts.vault.reload()?;
+        let received = ctx.accounts.vault.amount.checked_sub(balance).ok_or(VotingError::Overflow)?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.poll_id = poll_id;
+        escrow.voter = ctx.accounts.signer.key();
+        escrow.mint = mint;
+        escrow.amount = escrow.amount.checked_add(received).ok_or(VotingError::Overflow)?;
+        escrow.bump = ctx.bumps.escrow;
+
+        emit_cpi!(TokensDeposited {
+            poll_id,
+            voter: escrow.voter,
+            amount: received,
+            escrowed: escrow.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Returns all escrowed tokens and closes the vault. Tokens backing a
+    /// counted vote stay locked until the end time recorded when it was
+    /// counted, or until the vote is revoked or the poll stops being open;
+    /// the poll account may already be closed by then.
+    pub fn withdraw_tokens(ctx: Context<WithdrawTokens>, poll_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let now = Clock::get()?.unix_timestamp as u64;
+        if escrow.locked_weight > 0 && now <= escrow.locked_until && !ctx.accounts.poll.data_is_empty() {
+            // The live poll can only release the lock early, never extend it.
+            let poll = Poll::try_deserialize(&mut &ctx.accounts.poll.try_borrow_data()?[..])?;
+            require!(poll.status != PollStatus::Open, VotingError::TokensLocked);
+        }
+
+        let poll_id_bytes = poll_id.to_le_bytes();
+        let voter = ctx.accounts.signer.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"escrow", poll_id_bytes.as_ref(), voter.as_ref(), &[escrow.bump]]];
+        let amount = ctx.accounts.vault.amount;
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.voter_token_account.to_account_info(),
+                    authority: ctx.accounts.escrow.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+        // Fees withheld from the deposit keep a Token-2022 vault from closing.
+        let vault = ctx.accounts.vault.to_account_info();
+        if withheld_fees(&vault)? > 0 {
+            token_interface::harvest_withheld_tokens_to_mint(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    HarvestWithheldTokensToMint {
+                        token_program_id: ctx.accounts.token_program.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                    },
+                ),
+                vec![vault],
+            )?;
+        }
+        token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.signer.to_account_info(),
+                authority: ctx.accounts.escrow.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        emit_cpi!(TokensWithdrawn { poll_id, voter, amount });
+
+        Ok(())
+    }
+
+    /// Moves a single-choice vote to another candidate while voting is open.
+    /// The vote keeps its weight.
+    pub fn change_vote(ctx: Context<ChangeVote>, poll_id: u64, candidate_id: Pubkey) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require_voting_open(poll)?;
+        require!(poll.config.voting_method == VotingMethod::SingleChoice, VotingError::WrongVotingMethod);
+
+        let vote_record = &mut ctx.accounts.vote;
+        let weight = vote_record.weight;
+        let previous_candidate = &mut ctx.accounts.previous_candidate;
+        previous_candidate.votes = previous_candidate.votes.checked_sub(weight).ok_or(VotingError::Overflow)?;
+        let candidate = &mut ctx.accounts.candidate;
+        candidate.votes = candidate.votes.checked_add(weight).ok_or(VotingError::Overflow)?;
+        vote_record.candidate = candidate_id;
+
+        emit_cpi!(VoteChanged {
+            poll_id,
+            voter: vote_record.voter,
+            previous_candidate: previous_candidate.candidate_id,
+            candidate: candidate_id,
+            weight,
+            previous_candidate_votes: previous_candidate.votes,
+            candidate_votes: candidate.votes,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraws a single-choice vote while voting is open, removing its
+    /// weight from the tally and closing the `VoteRecord` so the voter may vote
+    /// again; its rent goes back to whoever paid for it. In token-weighted
+    /// polls this also unlocks the escrowed tokens. A vote that cast delegated
+    /// weight can be changed but not revoked.
+    pub fn revoke_vote(ctx: Context<RevokeVote>, poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require_voting_open(poll)?;
+        require!(poll.config.voting_method == VotingMethod::SingleChoice, VotingError::WrongVotingMethod);
+
+        // Delegators' markers point at this record, so it must stay.
+        require!(ctx.accounts.vote.delegated_weight == 0, VotingError::HasDelegators);
+
+        let weight = ctx.accounts.vote.weight;
+        let candidate = &mut ctx.accounts.candidate;
+        candidate.votes = candidate.votes.checked_sub(weight).ok_or(VotingError::Overflow)?;
+        poll.total_votes = poll.total_votes.checked_sub(weight).ok_or(VotingError::Overflow)?;
+        if let VoteWeight::TokenBalance { .. } = poll.config.vote_weight {
+            let escrow = ctx.accounts.escrow.as_mut().ok_or(VotingError::MissingEscrow)?;
+            escrow.locked_weight = 0;
+        }
+
+        emit_cpi!(VoteRevoked {
+            poll_id,
+            voter: ctx.accounts.signer.key(),
+            candidate: candidate.candidate_id,
+            weight,
+            candidate_votes: candidate.votes,
+            total_votes: poll.total_votes,
+        });
+
+        Ok(())
+    }
+
+    /// Closes the signer's `VoteRecord` (and `VoterCredits`, if supplied) once
+    /// the poll has been finalized or cancelled. The record's rent goes back to
+    /// whoever paid for it and the credits' rent to the voter.
+    pub fn close_vote_record(ctx: Context<CloseVoteRecord>, poll_id: u64) -> Result<()> {
+        emit_cpi!(VoteRecordClosed {
+            poll_id,
+            voter: ctx.accounts.voter.key(),
+            payer: ctx.accounts.payer.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Closes the signer's `VoteCommitment`, revealed or not, once the poll has
+    /// been finalized or cancelled and returns the rent to the voter.
+    pub fn close_commitment(ctx: Context<CloseCommitment>, poll_id: u64) -> Result<()> {
+        emit_cpi!(CommitmentClosed {
+            poll_id,
+            voter: ctx.accounts.voter.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Closes a candidate account once the poll has been finalized or cancelled
+    /// (or at any time for a rejected candidate) and returns its rent to
+    /// whoever paid for it.
+    pub fn close_candidate(ctx: Context<CloseCandidate>, poll_id: u64, candidate_id: Pubkey) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        if ctx.accounts.candidate.status != CandidateStatus::Rejected {
+            require!(
+                matches!(poll.status, PollStatus::Finalized | PollStatus::Cancelled),
+                VotingError::PollNotFinalized
+            );
+            poll.registered_candidates = poll.registered_candidates.checked_sub(1).ok_or(VotingError::Overflow)?;
+        }
+
+        emit_cpi!(CandidateClosed {
+            poll_id,
+            candidate: candidate_id,
+            payer: ctx.accounts.payer.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Closes a finalized or cancelled poll and returns its rent to the
+    /// authority. All of its candidates must be closed first; the `PollResult`,
+    /// if any, is kept.
+    pub fn close_poll_account(ctx: Context<ClosePollAccount>, poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(
+            matches!(poll.status, PollStatus::Finalized | PollStatus::Cancelled),
+            VotingError::PollNotFinalized
+        );
+        require!(poll.registered_candidates == 0, VotingError::CandidatesNotClosed);
+
+        emit_cpi!(PollAccountClosed {
+            poll_id,
+            authority: poll.authority,
+        });
+
+        Ok(())
+    }
+
+    /// Closes `delegator`'s `DelegatedVote` marker, left in place because the
+    /// delegator never overrode the delegate, once the poll has been finalized
+    /// or cancelled and returns its rent to whoever paid for it.
+    pub fn close_delegated_vote(ctx: Context<CloseDelegatedVote>, poll_id: u64, delegator: Pubkey) -> Result<()> {
+        emit_cpi!(DelegatedVoteClosed {
+            poll_id,
+            delegator,
+            payer: ctx.accounts.payer.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Closes a ranked-choice poll's `Runoff` once the poll has been finalized
+    /// or cancelled and returns its rent to whoever started the runoff.
+    pub fn close_runoff(ctx: Context<CloseRunoff>, poll_id: u64) -> Result<()> {
+        emit_cpi!(RunoffClosed {
+            poll_id,
+            payer: ctx.accounts.payer.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Closes an encrypted poll's `EncryptedTally` once the poll has been
+    /// finalized or cancelled and returns its rent to the poll authority that
+    /// configured it.
+    pub fn close_encrypted_tally(ctx: Context<CloseEncryptedTally>, poll_id: u64) -> Result<()> {
+        emit_cpi!(EncryptedTallyClosed {
+            poll_id,
+            payer: ctx.accounts.payer.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Closes `voter`'s `VoterNonce` for a poll once it has been finalized or
+    /// cancelled and returns its rent to the relayer that created it. The poll
+    /// can no longer accept ballots, so the nonce has nothing left to protect.
+    pub fn close_voter_nonce(ctx: Context<CloseVoterNonce>, poll_id: u64, voter: Pubkey) -> Result<()> {
+        emit_cpi!(VoterNonceClosed {
+            poll_id,
+            voter,
+            payer: ctx.accounts.payer.key(),
+        });
+
+        Ok(())
+    }
+}
+
+fn register_candidate(poll: &mut Poll, candidate: &mut Candidate, candidate_id: Pubkey, name: String, description: String, status: CandidateStatus) -> Result<()> {
+    require!(name.len() <= MAX_NAME_LEN, VotingError::NameTooLong);
+    require!(description.len() <= MAX_DESCRIPTION_LEN, VotingError::DescriptionTooLong);
+    require!(poll.status == PollStatus::Draft, VotingError::PollNotDraft);
+    require!(poll.registered_candidates < poll.candidates, VotingError::CandidateLimitReached);
+    poll.registered_candidates = poll.registered_candidates.checked_add(1).ok_or(VotingError::Overflow)?;
+
+    candidate.poll_id = poll.poll_id;
+    candidate.candidate_id = candidate_id;
+    candidate.name = name;
+    candidate.description = description;
+    candidate.votes = 0;
+    candidate.status = status;
+
+    Ok(())
+}
+
+fn validate_poll_config(config: &PollConfig, candidates: u64) -> Result<()> {
+    match config.voting_method {
+        VotingMethod::RankedChoice => require!(candidates <= MAX_RANKED_CANDIDATES, VotingError::CandidateLimitReached),
+        VotingMethod::Approval { max_selections } => require!(max_selections > 0, VotingError::InvalidPollConfig),
+        VotingMethod::Quadratic { credit_budget } => require!(credit_budget > 0, VotingError::InvalidPollConfig),
+        VotingMethod::CommitReveal { reveal_duration } => {
+            require!(reveal_duration > 0, VotingError::InvalidPollConfig);
+            require!(
+                config.vote_weight == VoteWeight::OnePerVoter && config.eligibility == Eligibility::Open,
+                VotingError::InvalidPollConfig
+            );
+        }
+        VotingMethod::Encrypted => {
+            require!(candidates <= MAX_ENCRYPTED_CANDIDATES, VotingError::CandidateLimitReached);
+            require!(
+                config.vote_weight == VoteWeight::OnePerVoter && config.eligibility == Eligibility::Open,
+                VotingError::InvalidPollConfig
+            );
+        }
+        VotingMethod::SingleChoice => {}
+    }
+    if let VoteWeight::TokenBalance { .. } = config.vote_weight {
+        require!(config.voting_method == VotingMethod::SingleChoice, VotingError::InvalidPollConfig);
+    }
+    if let Eligibility::NftCollection { .. } = config.eligibility {
+        require!(config.voting_method == VotingMethod::SingleChoice, VotingError::InvalidPollConfig);
+        require!(config.vote_weight == VoteWeight::OnePerVoter, VotingError::InvalidPollConfig);
+    }
+    if let Eligibility::Allowlist { .. } = config.eligibility {
+        require!(config.voting_method == VotingMethod::SingleChoice, VotingError::InvalidPollConfig);
+    } else {
+        require!(config.vote_weight != VoteWeight::Snapshot, VotingError::InvalidPollConfig);
+    }
+    Ok(())
+}
+
+/// The key a single-choice `VoteRecord` is derived from: the NFT mint in
+/// collection-gated polls, so each NFT votes once, and the voter otherwise.
+fn ballot_key(poll: &Poll, voter: &Pubkey, nft_token_account: Option<&InterfaceAccount<TokenAccount>>) -> Pubkey {
+    match (poll.config.eligibility, nft_token_account) {
+        (Eligibility::NftCollection { .. }, Some(nft_token_account)) => nft_token_account.mint,
+        _ => *voter,
+    }
+}
+
+/// Checks that `voter` holds an NFT verified as a member of `collection`.
+fn require_collection_nft(
+    voter: &Pubkey,
+    collection: &Pubkey,
+    nft_token_account: Option<&InterfaceAccount<TokenAccount>>,
+    nft_metadata: Option<&Account<MetadataAccount>>,
+) -> Result<Pubkey> {
+    let (Some(token_account), Some(metadata)) = (nft_token_account, nft_metadata) else {
+        return err!(VotingError::MissingNft);
+    };
+    require_keys_eq!(token_account.owner, *voter, VotingError::NftNotHeld);
+    require!(token_account.amount > 0, VotingError::NftNotHeld);
+    require_keys_eq!(metadata.mint, token_account.mint, VotingError::NftNotInCollection);
+    let verified = metadata
+        .collection
+        .as_ref()
+        .is_some_and(|c| c.verified && c.key == *collection);
+    require!(verified, VotingError::NftNotInCollection);
+    Ok(token_account.mint)
+}
+
+/// The wallet a `vote` is cast for: the owner of the signer's session, if one
+/// is supplied, and the signer otherwise.
+fn voter_key(signer: &Signer, session: Option<&Account<SessionKey>>) -> Pubkey {
+    session.map_or(signer.key(), |session| session.owner)
+}
+
+fn require_session(session: &SessionKey, poll_id: u64) -> Result<()> {
+    require!(Clock::get()?.unix_timestamp < session.expires_at, VotingError::SessionExpired);
+    require!(
+        session.polls.is_empty() || session.polls.contains(&poll_id),
+        VotingError::SessionOutOfScope
+    );
+    Ok(())
+}
+
+/// The seed distinguishing a per-poll delegation from a global one.
+fn delegation_scope(poll_id: Option<u64>) -> Vec<u8> {
+    poll_id.map(|poll_id| poll_id.to_le_bytes().to_vec()).unwrap_or_default()
+}
+
+fn delegation_address(poll_id: Option<u64>, delegator: &Pubkey) -> Pubkey {
+    let scope = delegation_scope(poll_id);
+    Pubkey::find_program_address(&[b"delegation", scope.as_ref(), delegator.as_ref()], &crate::ID).0
+}
+
+/// The delegate `delegator` currently points at for `poll_id` (or globally),
+/// read from the next delegation PDAs in `accounts`.
+fn next_delegate<'a, 'info: 'a>(
+    accounts: &mut impl Iterator<Item = &'a AccountInfo<'info>>,
+    poll_id: Option<u64>,
+    delegator: &Pubkey,
+) -> Result<Option<Pubkey>> {
+    for scope in poll_id.map(Some).into_iter().chain([None]) {
+        let info = accounts.next().ok_or(VotingError::IncompleteDelegationChain)?;
+        require_keys_eq!(info.key(), delegation_address(scope, delegator), VotingError::InvalidDelegation);
+        if !info.data_is_empty() {
+            let delegation = Delegation::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            return Ok(Some(delegation.delegate));
+        }
+    }
+    Ok(None)
+}
+
+fn require_acyclic_chain(remaining: &[AccountInfo], poll_id: Option<u64>, delegator: &Pubkey, delegate: &Pubkey) -> Result<()> {
+    let mut accounts = remaining.iter();
+    let mut current = *delegate;
+    let mut hops = 1;
+    loop {
+        require_keys_neq!(current, *delegator, VotingError::DelegationCycle);
+        match next_delegate(&mut accounts, poll_id, &current)? {
+            None => return Ok(()),
+            Some(next) => {
+                hops += 1;
+                require!(hops <= MAX_DELEGATION_DEPTH, VotingError::DelegationChainTooLong);
+                current = next;
+            }
+        }
+    }
+}
+
+/// Creates the PDA at `account`, owned by this program and funded by `payer`.
+/// Like Anchor's `init`, lamports already sent to the address are topped up
+/// rather than making creation fail.
+fn create_program_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    if account.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount { from: payer.clone(), to: account.clone() },
+                &[seeds],
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let top_up = rent.saturating_sub(account.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer { from: payer.clone(), to: account.clone() },
+            ),
+            top_up,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate { account_to_allocate: account.clone() },
+            &[seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign { account_to_assign: account.clone() },
+            &[seeds],
+        ),
+        &crate::ID,
+    )
+}
+
+/// Counts the delegators whose weight `delegate` casts in `poll` and returns
+/// their combined weight; `payer` pays for the markers. Each delegator is
+/// supplied in `remaining` as:
+///
+/// 1. its `Delegation`, pointing at the voter or a delegator listed earlier;
+/// 2. for a global delegation, its (empty) per-poll delegation PDA;
+/// 3. its (empty) `VoteRecord` PDA, proving it has not voted directly;
+/// 4. in token-weighted polls, its `VoterEscrow`, which gets locked;
+/// 5. its (new) `DelegatedVote` PDA, created here so it is counted once.
+///
+/// The counted delegators form a tree rooted at `delegate`: each one points at
+/// an earlier entry and is never `delegate` itself. This is the only cycle
+/// check across scopes, since `delegate_vote` walks one scope at a time.
+fn count_delegators<'info>(
+    remaining: &'info [AccountInfo<'info>],
+    poll: &Poll,
+    delegate: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<u64> {
+    let poll_id_bytes = poll.poll_id.to_le_bytes();
+    let mut counted = vec![(*delegate, 0u8)];
+    let mut total = 0u64;
+    let mut accounts = remaining.iter();
+    while let Some(info) = accounts.next() {
+        let delegation = Account::<Delegation>::try_from(info)?;
+        let delegator = delegation.delegator;
+        match delegation.poll_id {
+            Some(scope) => require!(scope == poll.poll_id, VotingError::InvalidDelegation),
+            // A per-poll delegation would take precedence.
+            None => {
+                let poll_delegation = accounts.next().ok_or(VotingError::IncompleteDelegationChain)?;
+                require_keys_eq!(
+                    poll_delegation.key(),
+                    delegation_address(Some(poll.poll_id), &delegator),
+                    VotingError::InvalidDelegation
+                );
+                require!(poll_delegation.data_is_empty(), VotingError::InvalidDelegation);
+            }
+        }
+        require_keys_neq!(delegator, *delegate, VotingError::DelegationCycle);
+        require!(counted.iter().all(|(key, _)| *key != delegator), VotingError::InvalidDelegation);
+        let depth = counted
+            .iter()
+            .find(|(key, _)| *key == delegation.delegate)
+            .map(|(_, depth)| depth + 1)
+            .ok_or(VotingError::InvalidDelegation)?;
+        require!(depth <= MAX_DELEGATION_DEPTH, VotingError::DelegationChainTooLong);
+
+        let vote_record = accounts.next().ok_or(VotingError::IncompleteDelegationChain)?;
+        let (vote_address, _) = Pubkey::find_program_address(&[b"vote", poll_id_bytes.as_ref(), delegator.as_ref()], &crate::ID);
+        require_keys_eq!(vote_record.key(), vote_address, VotingError::InvalidDelegation);
+        require!(vote_record.data_is_empty(), VotingError::DelegatorAlreadyVoted);
+
+        let weight = match poll.config.vote_weight {
+            VoteWeight::TokenBalance { mint } => {
+                let escrow_info = accounts.next().ok_or(VotingError::IncompleteDelegationChain)?;
+                let mut escrow = Account::<VoterEscrow>::try_from(escrow_info)?;
+                require!(escrow.voter == delegator && escrow.poll_id == poll.poll_id, VotingError::InvalidDelegation);
+                require_keys_eq!(escrow.mint, mint, VotingError::GovernanceMintMismatch);
+                require!(escrow.amount > 0, VotingError::NoVotingWeight);
+                escrow.locked_weight = escrow.amount;
+                escrow.locked_until = poll.end_time;
+                escrow.exit(&crate::ID)?;
+                escrow.amount
+            }
+            _ => 1,
+        };
+
+        let marker = accounts.next().ok_or(VotingError::IncompleteDelegationChain)?;
+        let (marker_address, bump) =
+            Pubkey::find_program_address(&[b"delegated", poll_id_bytes.as_ref(), delegator.as_ref()], &crate::ID);
+        require_keys_eq!(marker.key(), marker_address, VotingError::InvalidDelegation);
+        create_program_account(
+            payer,
+            marker,
+            system_program,
+            8 + DelegatedVote::INIT_SPACE,
+            &[b"delegated", poll_id_bytes.as_ref(), delegator.as_ref(), &[bump]],
+        )?;
+        DelegatedVote {
+            poll_id: poll.poll_id,
+            delegator,
+            delegate: *delegate,
+            payer: payer.key(),
+            weight,
+        }
+        .try_serialize(&mut &mut marker.try_borrow_mut_data()?[..])?;
+
+        counted.push((delegator, depth));
+        total = total.checked_add(weight).ok_or(VotingError::Overflow)?;
+    }
+    Ok(total)
+}
+
+/// Takes a delegator's weight back from the delegate who cast it, ahead of
+/// the delegator's own vote for `candidate_id`.
+fn override_delegated_vote(accounts: &mut Vote, poll_id: u64, candidate_id: Pubkey, marker: &DelegatedVote) -> Result<()> {
+    let delegate_vote = accounts.delegate_vote.as_ref().ok_or(VotingError::MissingDelegateVote)?;
+    let (delegate_vote_address, _) =
+        Pubkey::find_program_address(&[b"vote", poll_id.to_le_bytes().as_ref(), marker.delegate.as_ref()], &crate::ID);
+    require_keys_eq!(delegate_vote.key(), delegate_vote_address, VotingError::MissingDelegateVote);
+
+    let mut record = VoteRecord::try_deserialize(&mut &delegate_vote.try_borrow_data()?[..])?;
+    record.weight = record.weight.checked_sub(marker.weight).ok_or(VotingError::Overflow)?;
+    record.delegated_weight = record.delegated_weight.checked_sub(marker.weight).ok_or(VotingError::Overflow)?;
+    record.try_serialize(&mut &mut delegate_vote.try_borrow_mut_data()?[..])?;
+
+    let delegate_candidate = if record.candidate == candidate_id {
+        require!(accounts.delegate_candidate.is_none(), VotingError::MissingDelegateVote);
+        &mut accounts.candidate
+    } else {
+        let delegate_candidate = accounts.delegate_candidate.as_mut().ok_or(VotingError::MissingDelegateVote)?;
+        require!(
+            delegate_candidate.poll_id == poll_id && delegate_candidate.candidate_id == record.candidate,
+            VotingError::MissingDelegateVote
+        );
+        delegate_candidate
+    };
+    delegate_candidate.votes = delegate_candidate.votes.checked_sub(marker.weight).ok_or(VotingError::Overflow)?;
+    accounts.poll.total_votes = accounts.poll.total_votes.checked_sub(marker.weight).ok_or(VotingError::Overflow)?;
+    Ok(())
+}
+
+/// Transfer fees withheld in a Token-2022 account; always 0 for SPL Token.
+fn withheld_fees(token_account: &AccountInfo) -> Result<u64> {
+    let data = token_account.try_borrow_data()?;
+    let state = StateWithExtensions::<SplTokenAccount>::unpack(&data)?;
+    Ok(state.get_extension::<TransferFeeAmount>().map_or(0, |fee| u64::from(fee.withheld_amount)))
+}
+
+/// Closes a program-owned account that is not an Anchor `Account` field.
+fn close_marker(account: &AccountInfo, destination: &AccountInfo) -> Result<()> {
+    let lamports = account.lamports();
+    **destination.try_borrow_mut_lamports()? = destination.lamports().checked_add(lamports).ok_or(VotingError::Overflow)?;
+    **account.try_borrow_mut_lamports()? = 0;
+    account.assign(&system_program::ID);
+    account.resize(0)?;
+    Ok(())
+}
+
+/// Loads the `Candidate` accounts of a multi-candidate ballot, which must be
+/// supplied in `remaining_accounts` in the same order as `candidate_ids`.
+fn load_ballot_candidates<'info>(remaining_accounts: &'info [AccountInfo<'info>], poll_id: u64, candidate_ids: &[Pubkey]) -> Result<Vec<Account<'info, Candidate>>> {
+    require!(remaining_accounts.len() == candidate_ids.len(), VotingError::InvalidBallot);
+
+    let mut candidates = Vec::with_capacity(candidate_ids.len());
+    for (index, (candidate_id, account_info)) in candidate_ids.iter().zip(remaining_accounts.iter()).enumerate() {
+        require!(!candidate_ids[..index].contains(candidate_id), VotingError::DuplicateCandidate);
+        let candidate = Account::<Candidate>::try_from(account_info)?;
+        require_keys_eq!(candidate.candidate_id, *candidate_id, VotingError::UnknownCandidate);
+        require!(candidate.poll_id == poll_id, VotingError::CandidatePollMismatch);
+        require!(candidate.status == CandidateStatus::Approved, VotingError::CandidateNotApproved);
+        candidates.push(candidate);
+    }
+    Ok(candidates)
+}
+
+/// Loads the `Candidate` accounts of an approval or quadratic `vote` but the
+/// first, which `vote` takes as `candidate`.
+fn load_other_ballot_candidates<'info>(remaining_accounts: &'info [AccountInfo<'info>], poll_id: u64, candidate_ids: &[Pubkey]) -> Result<Vec<Account<'info, Candidate>>> {
+    let (first, others) = candidate_ids.split_first().ok_or(VotingError::InvalidBallot)?;
+    require!(!others.contains(first), VotingError::DuplicateCandidate);
+    load_ballot_candidates(remaining_accounts, poll_id, others)
+}
+
+/// The commitment `commit_vote` expects for revealing `candidate_id` with `salt`.
+fn commitment_hash(poll_id: u64, voter: &Pubkey, candidate_id: &Pubkey, salt: &[u8; 32]) -> [u8; 32] {
+    hashv(&[&poll_id.to_le_bytes(), voter.as_ref(), candidate_id.as_ref(), salt]).to_bytes()
+}
+
+/// When ballots stop being counted: `end_time`, or the end of the reveal
+/// window in a commit-reveal poll.
+fn counting_end_time(poll: &Poll) -> u64 {
+    match poll.config.voting_method {
+        VotingMethod::CommitReveal { reveal_duration } => poll.end_time.saturating_add(reveal_duration),
+        _ => poll.end_time,
+    }
+}
+
+fn require_voting_open(poll: &Poll) -> Result<()> {
+    require!(poll.status == PollStatus::Open, VotingError::PollNotOpen);
+    let now = Clock::get()?.unix_timestamp as u64;
+    require!(now >= poll.start_time, VotingError::PollNotStarted);
+    require!(now <= poll.end_time, VotingError::PollEnded);
+    Ok(())
+}
+
+/// Sorts tallies by votes, descending, with ties ordered by candidate id.
+fn rank_tallies(tallies: &mut [CandidateTally]) {
+    tallies.sort_by(|a, b| b.votes.cmp(&a.votes).then_with(|| a.candidate_id.cmp(&b.candidate_id)));
+}
+
+/// Candidates sharing the highest vote count in already ranked `tallies`.
+fn leading_candidates(tallies: &[CandidateTally]) -> Vec<Pubkey> {
+    let top_votes = tallies.first().map_or(0, |tally| tally.votes);
+    tallies
+        .iter()
+        .take_while(|tally| tally.votes == top_votes)
+        .map(|tally| tally.candidate_id)
+        .collect()
+}
+
+/// Reads the tally of every registered (non-rejected) candidate of `poll`,
+/// each of which must be in `remaining_accounts` exactly once. Rejected
+/// candidates are skipped.
+fn load_candidate_tallies<'info>(remaining_accounts: &'info [AccountInfo<'info>], poll: &Poll) -> Result<Vec<CandidateTally>> {
+    let mut tallies: Vec<CandidateTally> = Vec::with_capacity(poll.registered_candidates as usize);
+    for account_info in remaining_accounts.iter() {
+        let candidate = Account::<Candidate>::try_from(account_info)?;
+        require!(candidate.poll_id == poll.poll_id, VotingError::CandidatePollMismatch);
+        if candidate.status == CandidateStatus::Rejected {
+            continue;
+        }
+        require!(
+            tallies.iter().all(|tally| tally.candidate_id != candidate.candidate_id),
+            VotingError::DuplicateCandidate
+        );
+        tallies.push(CandidateTally {
+            candidate_id: candidate.candidate_id,
+            votes: candidate.votes,
+        });
+    }
+    require!(tallies.len() as u64 == poll.registered_candidates, VotingError::IncompleteCandidates);
+    Ok(tallies)
+}
+
+/// Settles a fully counted runoff round and returns the candidate it
+/// eliminated, if any. A candidate with a strict majority of the continuing
+/// ballots wins; if every remaining candidate is tied they all win. Otherwise
+/// the last-placed candidate is eliminated (ties for last place eliminate the
+/// highest candidate id) and the next round starts from zero. There are at
+/// most as many rounds as candidates.
+fn settle_runoff_round(runoff: &mut Runoff) -> Option<Pubkey> {
+    let mut tallies: Vec<CandidateTally> = runoff
+        .remaining
+        .iter()
+        .zip(runoff.votes.iter())
+        .map(|(candidate_id, votes)| CandidateTally {
+            candidate_id: *candidate_id,
+            votes: *votes,
+        })
+        .collect();
+    rank_tallies(&mut tallies);
+
+    let continuing = tallies.iter().map(|tally| tally.votes as u128).sum::<u128>();
+    let top_votes = tallies.first().map_or(0, |tally| tally.votes);
+    let last_votes = tallies.last().map_or(0, |tally| tally.votes);
+    let eliminated = if (top_votes as u128) * 2 > continuing || top_votes == last_votes {
+        runoff.winners = leading_candidates(&tallies);
+        None
+    } else {
+        // Ranked order puts the highest candidate id last among those tied for last place.
+        tallies.last().map(|tally| tally.candidate_id)
+    };
+    runoff.rounds.push(RunoffRound {
+        tallies,
+        exhausted: runoff.exhausted,
+        eliminated,
+    });
+
+    if let Some(eliminated) = eliminated {
+        runoff.remaining.retain(|candidate_id| *candidate_id != eliminated);
+        runoff.votes = vec![0; runoff.remaining.len()];
+        runoff.exhausted = 0;
+        runoff.counted = 0;
+    }
+    eliminated
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(_poll_id: u64)]
+pub struct InitializePoll<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + Poll::INIT_SPACE,
+        seeds = [b"poll".as_ref(), _poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    /// CHECK: the poll's `PollResult`, which must be empty.
+    #[account(seeds = [b"result".as_ref(), _poll_id.to_le_bytes().as_ref()], bump)]
+    pub result: UncheckedAccount<'info>,
+    /// Required when the poll is token-weighted; SPL Token or Token-2022.
+    pub governance_mint: Option<InterfaceAccount<'info, Mint>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct UpdatePoll<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = authority @ VotingError::Unauthorized
+    )]
+    pub poll: Account<'info, Poll>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct CancelPoll<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = authority @ VotingError::Unauthorized
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        init,
+        payer = authority,
+        space = PollResult::space_for(0, 0),
+        seeds = [b"result".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub result: Account<'info, PollResult>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct StartRunoff<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        init,
+        payer = payer,
+        space = Runoff::space(poll.registered_candidates as usize),
+        seeds = [b"runoff".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub runoff: Account<'info, Runoff>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct CountRankedBallots<'info> {
+    #[account(
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        seeds = [b"runoff".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub runoff: Account<'info, Runoff>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct FinalizePoll<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        init,
+        payer = payer,
+        space = PollResult::space(&poll),
+        seeds = [b"result".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub result: Account<'info, PollResult>,
+    /// Instant-runoff count of a ranked-choice poll; omitted for other polls.
+    #[account(
+        seeds = [b"runoff".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub runoff: Option<Account<'info, Runoff>>,
+    /// Tally of an encrypted poll; omitted for other polls.
+    #[account(
+        seeds = [b"encrypted_tally".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub encrypted_tally: Option<Account<'info, EncryptedTally>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct InitializeCandidate<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + Candidate::INIT_SPACE,
+        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub candidate: Account<'info, Candidate>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, candidate_id: Pubkey)]
+pub struct AddCandidate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = authority @ VotingError::Unauthorized
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Candidate::INIT_SPACE,
+        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), candidate_id.as_ref()],
+        bump
+    )]
+    pub candidate: Account<'info, Candidate>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, candidate_id: Pubkey)]
+pub struct ModerateCandidate<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = authority @ VotingError::Unauthorized,
+        constraint = poll.status == PollStatus::Draft @ VotingError::PollNotDraft
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), candidate_id.as_ref()],
+        bump,
+        constraint = candidate.status == CandidateStatus::Pending @ VotingError::CandidateNotPending
+    )]
+    pub candidate: Account<'info, Candidate>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, candidate_id: Pubkey, proof: Option<EligibilityProof>, choices: Option<BallotChoices>)]
+pub struct Vote<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    /// The signer's session, when it votes for the session's owner.
+    #[account(
+        seeds = [b"session".as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub session: Option<Account<'info, SessionKey>>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), candidate_id.as_ref()],
+        bump,
+        constraint = candidate.candidate_id == candidate_id @ VotingError::UnknownCandidate,
+        constraint = candidate.poll_id == poll.poll_id @ VotingError::CandidatePollMismatch,
+        constraint = candidate.status == CandidateStatus::Approved @ VotingError::CandidateNotApproved
+    )]
+    pub candidate: Account<'info, Candidate>,
+    /// The voter's token escrow; required in token-weighted polls.
+    #[account(
+        mut,
+        seeds = [
+            b"escrow".as_ref(),
+            poll_id.to_le_bytes().as_ref(),
+            voter_key(&signer, session.as_ref()).as_ref()
+        ],
+        bump = escrow.bump
+    )]
+    pub escrow: Option<Account<'info, VoterEscrow>>,
+    /// The voter's token account holding the NFT; required in collection-gated polls.
+    pub nft_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// Metaplex metadata of the NFT.
+    pub nft_metadata: Option<Account<'info, MetadataAccount>>,
+    /// CHECK: the voter's `DelegatedVote` marker, empty unless a delegate
+    /// already cast the voter's weight in this poll.
+    #[account(
+        mut,
+        seeds = [
+            b"delegated".as_ref(),
+            poll_id.to_le_bytes().as_ref(),
+            voter_key(&signer, session.as_ref()).as_ref()
+        ],
+        bump
+    )]
+    pub delegated_vote: UncheckedAccount<'info>,
+    /// CHECK: receives the rent of `delegated_vote` when it is overridden;
+    /// must match its `payer`.
+    #[account(mut)]
+    pub delegated_vote_payer: Option<UncheckedAccount<'info>>,
+    /// CHECK: the `VoteRecord` of the delegate named in `delegated_vote`,
+    /// checked against it in the handler.
+    #[account(mut)]
+    pub delegate_vote: Option<UncheckedAccount<'info>>,
+    /// The candidate that delegate voted for, unless it is `candidate`.
+    #[account(mut)]
+    pub delegate_candidate: Option<Account<'info, Candidate>>,
+    /// The voter's quadratic-voting credits; required for quadratic ballots.
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + VoterCredits::INIT_SPACE,
+        seeds = [
+            b"credits".as_ref(),
+            poll_id.to_le_bytes().as_ref(),
+            voter_key(&signer, session.as_ref()).as_ref()
+        ],
+        bump
+    )]
+    pub credits: Option<Account<'info, VoterCredits>>,
+    #[account(
+        init,
+        payer = signer,
+        space = BallotChoices::record_space(choices.as_ref()),
+        seeds = [
+            b"vote".as_ref(),
+            poll_id.to_le_bytes().as_ref(),
+            ballot_key(&poll, &voter_key(&signer, session.as_ref()), nft_token_account.as_ref()).as_ref()
+        ],
+        bump
+    )]
+    pub vote: Account<'info, VoteRecord>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateSession<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub session_key: Signer<'info>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + SessionKey::INIT_SPACE,
+        seeds = [b"session".as_ref(), session_key.key().as_ref()],
+        bump
+    )]
+    pub session: Account<'info, SessionKey>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct RevokeSession<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"session".as_ref(), session_key.as_ref()],
+        bump,
+        has_one = owner @ VotingError::Unauthorized
+    )]
+    pub session: Account<'info, SessionKey>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, candidate_id: Pubkey, voter: Pubkey)]
+pub struct VoteWithSignature<'info> {
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), candidate_id.as_ref()],
+        bump,
+        constraint = candidate.status == CandidateStatus::Approved @ VotingError::CandidateNotApproved
+    )]
+    pub candidate: Account<'info, Candidate>,
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + VoterNonce::INIT_SPACE,
+        seeds = [b"nonce".as_ref(), poll_id.to_le_bytes().as_ref(), voter.as_ref()],
+        bump
+    )]
+    pub voter_nonce: Account<'info, VoterNonce>,
+    /// CHECK: the voter's `DelegatedVote` marker, which must be empty.
+    #[account(
+        seeds = [b"delegated".as_ref(), poll_id.to_le_bytes().as_ref(), voter.as_ref()],
+        bump
+    )]
+    pub delegated_vote: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = relayer,
+        space = VoteRecord::space(0, 0),
+        seeds = [b"vote".as_ref(), poll_id.to_le_bytes().as_ref(), voter.as_ref()],
+        bump
+    )]
+    pub vote: Account<'info, VoteRecord>,
+    /// CHECK: the instructions sysvar, read for the Ed25519 instruction.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, rankings: Vec<Pubkey>)]
+pub struct VoteRanked<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        init,
+        payer = signer,
+        space = VoteRecord::space(rankings.len(), 32),
+        seeds = [b"vote".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub vote: Account<'info, VoteRecord>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct CommitVote<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + VoteCommitment::INIT_SPACE,
+        seeds = [b"commitment".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub commitment: Account<'info, VoteCommitment>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, candidate_id: Pubkey)]
+pub struct RevealVote<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), candidate_id.as_ref()],
+        bump,
+        constraint = candidate.status == CandidateStatus::Approved @ VotingError::CandidateNotApproved
+    )]
+    pub candidate: Account<'info, Candidate>,
+    #[account(
+        mut,
+        seeds = [b"commitment".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub commitment: Account<'info, VoteCommitment>,
+    #[account(
+        init,
+        payer = signer,
+        space = VoteRecord::space(0, 0),
+        seeds = [b"vote".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub vote: Account<'info, VoteRecord>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, trustees: Vec<Pubkey>, threshold: u8)]
+pub struct ConfigureEncryptedTally<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = authority @ VotingError::Unauthorized
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        init,
+        payer = authority,
+        space = EncryptedTally::space(trustees.len(), threshold as usize, poll.registered_candidates as usize),
+        seeds = [b"encrypted_tally".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct RegisterTrusteeKey<'info> {
+    pub trustee: Signer<'info>,
+    #[account(
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct VoteEncrypted<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+    #[account(
+        init,
+        payer = signer,
+        space = VoteRecord::space(0, 0),
+        seeds = [b"vote".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub vote: Account<'info, VoteRecord>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct PostDecryptionShare<'info> {
+    pub trustee: Signer<'info>,
+    #[account(
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct DecryptTally<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        seeds = [b"encrypted_tally".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct DepositTokens<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = signer,
+        token::token_program = token_program
+    )]
+    pub voter_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + VoterEscrow::INIT_SPACE,
+        seeds = [b"escrow".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, VoterEscrow>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        seeds = [b"vault".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct WithdrawTokens<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    /// CHECK: the poll PDA, read only while it still exists. It may end a
+    /// lock early but the escrow's `locked_until` bounds it either way.
+    #[account(
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: UncheckedAccount<'info>,
+    /// Writable so that Token-2022 transfer fees withheld in the vault can be
+    /// harvested to it before the vault is closed.
+    #[account(mut, address = escrow.mint @ VotingError::GovernanceMintMismatch)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program
+    )]
+    pub voter_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"escrow".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, VoterEscrow>,
+    #[account(
+        mut,
+        seeds = [b"vault".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: Option<u64>)]
+pub struct DelegateVote<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + Delegation::INIT_SPACE,
+        seeds = [b"delegation".as_ref(), delegation_scope(poll_id).as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: Option<u64>)]
+pub struct UndelegateVote<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"delegation".as_ref(), delegation_scope(poll_id).as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, candidate_id: Pubkey)]
+pub struct ChangeVote<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), vote.candidate.as_ref()],
+        bump
+    )]
+    pub previous_candidate: Account<'info, Candidate>,
+    #[account(
+        mut,
+        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), candidate_id.as_ref()],
+        bump,
+        constraint = candidate_id != vote.candidate @ VotingError::AlreadyVotedFor,
+        constraint = candidate.status == CandidateStatus::Approved @ VotingError::CandidateNotApproved
+    )]
+    pub candidate: Account<'info, Candidate>,
+    /// Keyed by the voter, or by the NFT mint in collection-gated polls.
+    #[account(
+        mut,
+        constraint = vote.voter == signer.key() @ VotingError::Unauthorized,
+        constraint = vote.poll_id == poll_id @ VotingError::VotePollMismatch
+    )]
+    pub vote: Account<'info, VoteRecord>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct RevokeVote<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), vote.candidate.as_ref()],
+        bump
+    )]
+    pub candidate: Account<'info, Candidate>,
+    /// Keyed by the voter, or by the NFT mint in collection-gated polls.
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer @ VotingError::PayerMismatch,
+        constraint = vote.voter == signer.key() @ VotingError::Unauthorized,
+        constraint = vote.poll_id == poll_id @ VotingError::VotePollMismatch
+    )]
+    pub vote: Account<'info, VoteRecord>,
+    /// CHECK: receives the rent refund; must match `vote.payer`.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    /// The signer's token escrow, unlocked by the revocation; required in
+    /// token-weighted polls.
+    #[account(
+        mut,
+        seeds = [b"escrow".as_ref(), poll_id.to_le_bytes().as_ref(), signer.key().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Option<Account<'info, VoterEscrow>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct CloseVoteRecord<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    /// Written once the poll is finalized or cancelled.
+    #[account(
+        seeds = [b"result".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub result: Account<'info, PollResult>,
+    /// Keyed by the voter, or by the NFT mint in collection-gated polls.
+    #[account(
+        mut,
+        close = payer,
+        has_one = voter @ VotingError::Unauthorized,
+        has_one = payer @ VotingError::PayerMismatch,
+        constraint = vote.poll_id == poll_id @ VotingError::VotePollMismatch
+    )]
+    pub vote: Account<'info, VoteRecord>,
+    /// CHECK: receives the rent refund; must match `vote.payer`.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    /// The voter's quadratic-voting credits, closed alongside the record.
+    #[account(
+        mut,
+        close = voter,
+        seeds = [b"credits".as_ref(), poll_id.to_le_bytes().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub credits: Option<Account<'info, VoterCredits>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct CloseCommitment<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    /// Written once the poll is finalized or cancelled.
+    #[account(
+        seeds = [b"result".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub result: Account<'info, PollResult>,
+    #[account(
+        mut,
+        close = voter,
+        seeds = [b"commitment".as_ref(), poll_id.to_le_bytes().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub commitment: Account<'info, VoteCommitment>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, candidate_id: Pubkey)]
+pub struct CloseCandidate<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = authority @ VotingError::Unauthorized
+    )]
+    pub poll: Account<'info, Poll>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"candidate".as_ref(), poll_id.to_le_bytes().as_ref(), candidate_id.as_ref()],
+        bump,
+        has_one = payer @ VotingError::PayerMismatch
+    )]
+    pub candidate: Account<'info, Candidate>,
+    /// CHECK: receives the rent refund; must match `candidate.payer`.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct ClosePollAccount<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"poll".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = authority @ VotingError::Unauthorized
+    )]
+    pub poll: Account<'info, Poll>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, delegator: Pubkey)]
+pub struct CloseDelegatedVote<'info> {
+    /// Written once the poll is finalized or cancelled.
+    #[account(
+        seeds = [b"result".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub result: Account<'info, PollResult>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"delegated".as_ref(), poll_id.to_le_bytes().as_ref(), delegator.as_ref()],
+        bump,
+        has_one = payer @ VotingError::PayerMismatch
+    )]
+    pub delegated_vote: Account<'info, DelegatedVote>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct CloseRunoff<'info> {
+    /// Written once the poll is finalized or cancelled.
+    #[account(
+        seeds = [b"result".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub result: Account<'info, PollResult>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"runoff".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = payer @ VotingError::PayerMismatch
+    )]
+    pub runoff: Account<'info, Runoff>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64)]
+pub struct CloseEncryptedTally<'info> {
+    /// Written once the poll is finalized or cancelled.
+    #[account(
+        seeds = [b"result".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub result: Account<'info, PollResult>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"encrypted_tally".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = payer @ VotingError::PayerMismatch
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, voter: Pubkey)]
+pub struct CloseVoterNonce<'info> {
+    /// Written once the poll is finalized or cancelled.
+    #[account(
+        seeds = [b"result".as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub result: Account<'info, PollResult>,
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"nonce".as_ref(), poll_id.to_le_bytes().as_ref(), voter.as_ref()],
+        bump,
+        has_one = payer @ VotingError::PayerMismatch
+    )]
+    pub voter_nonce: Account<'info, VoterNonce>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Poll {
+    pub poll_id: u64,
+    pub authority: Pubkey,
+    #[max_len(MAX_DESCRIPTION_LEN)]
+    pub description: String,
+    /// Maximum number of candidates that may register.
+    pub candidates: u64,
+    /// Number of candidates registered so far.
+    pub registered_candidates: u64,
+    /// Unix timestamp in seconds at which voting opens.
+    pub start_time: u64,
+    /// Unix timestamp in seconds after which votes are rejected.
+    pub end_time: u64,
+    pub config: PollConfig,
+    pub status: PollStatus,
+    /// Sum of all candidate tallies. Equals the number of ballots except in
+    /// approval and quadratic polls, where each selection or vote counts, and
+    /// token-weighted polls, where each ballot counts its weight. Stays 0 in an
+    /// encrypted poll until its tally is decrypted.
+    pub total_votes: u64,
+    /// Ballots committed in a commit-reveal poll, revealed or not.
+    pub commitments: u64,
+}
+
+/// Lifecycle of a poll. Candidates can only be registered and moderated in
+/// `Draft`, and votes are only accepted in `Open`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PollStatus {
+    Draft,
+    Open,
+    Closed,
+    Finalized,
+    Cancelled,
+}
+
+/// Options fixed when the poll is created.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct PollConfig {
+    pub candidate_mode: CandidateMode,
+    pub voting_method: VotingMethod,
+    pub vote_weight: VoteWeight,
+    pub eligibility: Eligibility,
+}
+
+/// Who may vote.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum Eligibility {
+    Open,
+    /// Wallets in an off-chain snapshot committed to by the Merkle `root`
+    /// (see [`merkle`]). Only supported with single-choice polls.
+    Allowlist { root: [u8; 32] },
+    /// Holders of an NFT verified as a member of the Metaplex `collection`.
+    /// Each NFT votes once, so a wallet holding several NFTs votes once per
+    /// NFT. Only supported with single-choice, one-per-voter polls.
+    NftCollection { collection: Pubkey },
+}
+
+/// How much a single ballot counts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum VoteWeight {
+    OnePerVoter,
+    /// Weighted by the voter's balance of `mint`, an SPL Token or Token-2022
+    /// mint, escrowed with `deposit_tokens`. Only supported with
+    /// `VotingMethod::SingleChoice`.
+    TokenBalance { mint: Pubkey },
+    /// Weighted by the voter's weight in the allowlist snapshot. Requires
+    /// `Eligibility::Allowlist`.
+    Snapshot,
+}
+
+/// A voter's allowlist entry and the sibling hashes from its leaf to the root.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EligibilityProof {
+    /// The weight attached to the voter in the snapshot; 1 when unweighted.
+    pub weight: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// How ballots are cast and counted.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum VotingMethod {
+    /// One candidate per ballot, cast with `vote`.
+    SingleChoice,
+    /// An ordered list of candidates per ballot, cast with `vote_ranked` and
+    /// decided by instant runoff.
+    RankedChoice,
+    /// Any subset of up to `max_selections` candidates per ballot, cast with
+    /// `vote` and `BallotChoices::Approval`; every selected candidate receives
+    /// one vote.
+    Approval { max_selections: u8 },
+    /// Votes spread across candidates with `vote` and
+    /// `BallotChoices::Quadratic`, where `n` votes for one candidate cost `n²`
+    /// of each voter's `credit_budget`.
+    Quadratic { credit_budget: u64 },
+    /// Sealed ballots committed with `commit_vote` while the poll is open and
+    /// revealed with `reveal_vote` in the `reveal_duration` seconds after
+    /// `end_time`. Unrevealed ballots are not counted.
+    CommitReveal { reveal_duration: u64 },
+    /// Ballots encrypted to the trustees' joint key and cast with
+    /// `vote_encrypted` (see [`elgamal`]). Only the sum of all ballots is ever
+    /// decrypted, with `post_decryption_share` and `decrypt_tally`.
+    Encrypted,
+}
+
+/// Who may register candidates for a poll.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum CandidateMode {
+    /// Candidates register themselves through `initialize_candidate`.
+    SelfNomination,
+    /// The poll authority registers candidates through `add_candidate`.
+    AuthorityManaged,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Candidate {
+    pub poll_id: u64,
+    pub candidate_id: Pubkey,
+    #[max_len(MAX_NAME_LEN)]
+    pub name: String,
+    #[max_len(MAX_DESCRIPTION_LEN)]
+    pub description: String,
+    pub votes: u64,
+    pub status: CandidateStatus,
+    /// Account that paid the rent and is refunded when the candidate is closed.
+    pub payer: Pubkey,
+}
+
+/// Moderation state of a candidate. Self-nominated candidates start `Pending`;
+/// only `Approved` candidates can receive votes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum CandidateStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[account]
+pub struct VoteRecord {
+    pub voter: Pubkey,
+    /// Account that paid the rent and is refunded when the record is closed:
+    /// the voter, or the relayer of a signed ballot.
+    pub payer: Pubkey,
+    pub poll_id: u64,
+    /// The chosen candidate, or the first preference of a ranked ballot. Unset
+    /// for an encrypted ballot.
+    pub candidate: Pubkey,
+    /// Votes added to the chosen candidate; the token balance in token-weighted
+    /// polls. Includes `delegated_weight`.
+    pub weight: u64,
+    /// Weight cast on behalf of delegators.
+    pub delegated_weight: u64,
+    /// The NFT this ballot was cast with in a collection-gated poll.
+    pub nft_mint: Option<Pubkey>,
+    /// Instant-runoff rounds this ranked ballot has been counted in.
+    pub rounds_counted: u8,
+    pub ballot: Ballot,
+}
+
+impl VoteRecord {
+    /// Space for a record whose ballot holds `choices` entries of `choice_size` bytes.
+    pub fn space(choices: usize, choice_size: usize) -> usize {
+        8 + 32 + 32 + 8 + 32 + 8 + 8 + 33 + 1 + 1 + 4 + choices * choice_size
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum Ballot {
+    Single,
+    /// Candidate ids from most to least preferred.
+    Ranked { rankings: Vec<Pubkey> },
+    /// Every candidate the voter approved of.
+    Approval { selections: Vec<Pubkey> },
+    /// Votes given to each candidate.
+    Quadratic { allocations: Vec<QuadraticVote> },
+    /// An encrypted ballot, counted only in the poll's `EncryptedTally`.
+    Encrypted,
+}
+
+/// The whole ballot of an approval or quadratic `vote`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum BallotChoices {
+    /// Every candidate the voter approves of, up to `max_selections`.
+    Approval { selections: Vec<Pubkey> },
+    /// Votes given to each candidate.
+    Quadratic { allocations: Vec<QuadraticVote> },
+}
+
+impl BallotChoices {
+    /// Space for the `VoteRecord` of a `vote` carrying `choices`.
+    pub fn record_space(choices: Option<&Self>) -> usize {
+        match choices {
+            None => VoteRecord::space(0, 0),
+            Some(BallotChoices::Approval { selections }) => VoteRecord::space(selections.len(), 32),
+            Some(BallotChoices::Quadratic { allocations }) => VoteRecord::space(allocations.len(), QuadraticVote::INIT_SPACE),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct QuadraticVote {
+    pub candidate_id: Pubkey,
+    pub votes: u64,
+}
+
+/// Quadratic-voting credits of one voter in one poll.
+#[account]
+#[derive(InitSpace)]
+pub struct VoterCredits {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub budget: u64,
+    pub spent: u64,
+}
+
+/// Governance tokens one voter has locked for a token-weighted poll. The
+/// tokens sit in the `vault` token account owned by this PDA.
+#[account]
+#[derive(InitSpace)]
+pub struct VoterEscrow {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub mint: Pubkey,
+    /// Tokens held in the vault.
+    pub amount: u64,
+    /// Weight of the voter's counted vote; non-zero while the tokens are locked.
+    pub locked_weight: u64,
+    /// `end_time` of the poll when the vote was counted; the lock lapses after it.
+    pub locked_until: u64,
+    pub bump: u8,
+}
+
+/// An ephemeral key its `owner` has authorized to cast `vote`s on its behalf.
+#[account]
+#[derive(InitSpace)]
+pub struct SessionKey {
+    pub owner: Pubkey,
+    pub session_key: Pubkey,
+    /// Unix timestamp in seconds from which the key is rejected.
+    pub expires_at: i64,
+    /// Polls the key may vote in; any poll when empty.
+    #[max_len(MAX_SESSION_POLLS)]
+    pub polls: Vec<u64>,
+}
+
+/// Replay protection for ballots signed off-chain: the nonce the voter's next
+/// signed ballot in the poll must carry.
+#[account]
+#[derive(InitSpace)]
+pub struct VoterNonce {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    /// The relayer that created this account and is refunded when it is closed.
+    pub payer: Pubkey,
+    pub nonce: u64,
+}
+
+/// A voter's delegation, global (`poll_id` is `None`) or for one poll.
+#[account]
+#[derive(InitSpace)]
+pub struct Delegation {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub poll_id: Option<u64>,
+}
+
+/// Left by `vote` for each delegator whose weight a delegate cast, so the
+/// delegator is counted once and can override it by voting directly.
+#[account]
+#[derive(InitSpace)]
+pub struct DelegatedVote {
+    pub poll_id: u64,
+    pub delegator: Pubkey,
+    /// The delegate whose `VoteRecord` includes the weight.
+    pub delegate: Pubkey,
+    /// Paid for this account and is refunded when it is closed.
+    pub payer: Pubkey,
+    pub weight: u64,
+}
+
+/// A sealed ballot in a commit-reveal poll.
+#[account]
+#[derive(InitSpace)]
+pub struct VoteCommitment {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    /// `sha256(poll_id || voter || candidate_id || salt)`.
+    pub commitment: [u8; 32],
+    pub revealed: bool,
+}
+
+/// An exponential ElGamal ciphertext `(r·G, m·G + r·Y)` of a count `m`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct Ciphertext {
+    pub c1: [u8; 32],
+    pub c2: [u8; 32],
+}
+
+/// Proof that a ciphertext encrypts 0 or 1; see [`elgamal::verify_bit`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BitProof {
+    pub c0: [u8; 32],
+    pub c1: [u8; 32],
+    pub z0: [u8; 32],
+    pub z1: [u8; 32],
+}
+
+/// Proof of knowledge of a discrete logarithm; see [`elgamal::verify_key`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SchnorrProof {
+    pub c: [u8; 32],
+    pub z: [u8; 32],
+}
+
+/// Proof that two pairs of points share a discrete logarithm; see
+/// [`elgamal::verify_sum`] and [`elgamal::verify_share`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DleqProof {
+    pub c: [u8; 32],
+    pub z: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EncryptedChoice {
+    pub ciphertext: Ciphertext,
+    pub proof: BitProof,
+}
+
+/// One ciphertext per candidate, voting for exactly one of them.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EncryptedBallot {
+    pub choices: Vec<EncryptedChoice>,
+    pub sum_proof: DleqProof,
+}
+
+/// A trustee's decryption share `s_i·C1` of one candidate's ciphertext.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DecryptionShare {
+    pub share: [u8; 32],
+    pub proof: DleqProof,
+}
+
+/// A trustee of an encrypted poll; trustee `i` in the list holds the key
+/// share at index `i + 1`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Trustee {
+    pub authority: Pubkey,
+    /// Whether the trustee's key commitments have been registered.
+    pub registered: bool,
+    /// The trustee's decryption share of each candidate's ciphertext; empty
+    /// until posted.
+    pub shares: Vec<[u8; 32]>,
+}
+
+impl Trustee {
+    pub fn space(candidates: usize) -> usize {
+        32 + 1 + (4 + candidates * 32)
+    }
+
+    pub fn posted(&self) -> bool {
+        !self.shares.is_empty()
+    }
+}
+
+/// Homomorphic tally of an encrypted poll.
+#[account]
+pub struct EncryptedTally {
+    pub poll_id: u64,
+    /// Paid for this account and is refunded when it is closed.
+    pub payer: Pubkey,
+    /// The poll key `Y = A_0`, complete once every trustee has registered.
+    pub public_key: [u8; 32],
+    /// Decryption shares needed to read the tally.
+    pub threshold: u8,
+    pub trustees: Vec<Trustee>,
+    /// `A_k`, the sum of the registered trustees' `k`-th coefficient commitments.
+    pub commitments: Vec<[u8; 32]>,
+    /// Candidates in ballot order.
+    pub candidate_ids: Vec<Pubkey>,
+    /// Sum of every ballot's ciphertext, per candidate.
+    pub ciphertexts: Vec<Ciphertext>,
+    pub ballots: u64,
+    /// Whether `decrypt_tally` has written the totals to the candidates.
+    pub decrypted: bool,
+}
+
+impl EncryptedTally {
+    pub fn space(trustees: usize, threshold: usize, candidates: usize) -> usize {
+        8 + 8
+            + 32
+            + 32
+            + 1
+            + (4 + trustees * Trustee::space(candidates))
+            + (4 + threshold * 32)
+            + (4 + candidates * 32)
+            + (4 + candidates * Ciphertext::INIT_SPACE)
+            + 8
+            + 1
+    }
+}
+
+/// Instant-runoff count of a ranked-choice poll, run round by round over its
+/// `VoteRecord`s so that no single transaction needs every ballot.
+#[account]
+pub struct Runoff {
+    pub poll_id: u64,
+    /// Paid for this account and is refunded when it is closed.
+    pub payer: Pubkey,
+    /// Candidates still in the running.
+    pub remaining: Vec<Pubkey>,
+    /// Ballots counted this round for each candidate in `remaining`.
+    pub votes: Vec<u64>,
+    /// Ballots counted this round with no remaining candidate ranked.
+    pub exhausted: u64,
+    /// Ballots counted this round.
+    pub counted: u64,
+    /// Settled rounds.
+    pub rounds: Vec<RunoffRound>,
+    /// Set by the round that decides the poll.
+    pub winners: Vec<Pubkey>,
+}
+
+impl Runoff {
+    pub fn space(candidates: usize) -> usize {
+        8 + 8
+            + 32
+            + (4 + candidates * 32)
+            + (4 + candidates * 8)
+            + 8
+            + 8
+            + (4 + candidates * RunoffRound::space(candidates))
+            + (4 + candidates * 32)
+    }
+}
+
+/// Certified outcome of a finalized poll, or the empty marker of a cancelled
+/// one. `tallies` is ranked by votes, descending, with ties ordered by
+/// candidate id.
+#[account]
+pub struct PollResult {
+    pub poll_id: u64,
+    pub total_votes: u64,
+    pub tallies: Vec<CandidateTally>,
+    pub winners: Vec<Pubkey>,
+    pub is_tie: bool,
+    pub finalized_at: i64,
+    /// Instant-runoff rounds of a ranked-choice poll; empty otherwise.
+    pub rounds: Vec<RunoffRound>,
+    /// Commitments never revealed in a commit-reveal poll, which are not
+    /// counted in `total_votes`.
+    pub unrevealed: u64,
+    /// Set by `cancel_poll`, which leaves everything else empty.
+    pub cancelled: bool,
+}
+
+impl PollResult {
+    pub fn space(poll: &Poll) -> usize {
+        let candidates = poll.registered_candidates as usize;
+        let rounds = match poll.config.voting_method {
+            VotingMethod::RankedChoice => candidates,
+            _ => 0,
+        };
+        Self::space_for(candidates, rounds)
+    }
+
+    /// Space for a result with `candidates` tallies and `rounds` runoff rounds.
+    pub fn space_for(candidates: usize, rounds: usize) -> usize {
+        8 + 8
+            + 8
+            + (4 + candidates * CandidateTally::INIT_SPACE)
+            + (4 + candidates * 32)
+            + 1
+            + 8
+            + (4 + rounds * RunoffRound::space(candidates))
+            + 8
+            + 1
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RunoffRound {
+    /// Tallies of the candidates still in the running, ranked.
+    pub tallies: Vec<CandidateTally>,
+    /// Ballots with no remaining candidate ranked.
+    pub exhausted: u64,
+    pub eliminated: Option<Pubkey>,
+}
+
+impl RunoffRound {
+    pub fn space(candidates: usize) -> usize {
+        (4 + candidates * CandidateTally::INIT_SPACE) + 8 + (1 + 32)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct CandidateTally {
+    pub candidate_id: Pubkey,
+    pub votes: u64,
+}
+
+#[event]
+pub struct PollCreated {
+    pub poll_id: u64,
+    pub authority: Pubkey,
+    pub description: String,
+    pub candidates: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub config: PollConfig,
+}
+
+#[event]
+pub struct PollUpdated {
+    pub poll_id: u64,
+    pub description: String,
+    pub candidates: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+#[event]
+pub struct PollStatusChanged {
+    pub poll_id: u64,
+    pub status: PollStatus,
+}
+
+#[event]
+pub struct CandidateRegistered {
+    pub poll_id: u64,
+    pub candidate_id: Pubkey,
+    pub name: String,
+    pub description: String,
+    pub registered_candidates: u64,
+}
+
+#[event]
+pub struct CandidateStatusChanged {
+    pub poll_id: u64,
+    pub candidate_id: Pubkey,
+    pub status: CandidateStatus,
+}
+
+#[event]
+pub struct VoteCast {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub candidate: Pubkey,
+    pub weight: u64,
+    pub delegated_weight: u64,
+    pub candidate_votes: u64,
+    pub total_votes: u64,
+}
+
+#[event]
+pub struct RankedVoteCast {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub rankings: Vec<Pubkey>,
+    pub total_votes: u64,
+}
+
+#[event]
+pub struct RunoffRoundCounted {
+    pub poll_id: u64,
+    /// Rounds settled so far, including this one.
+    pub round: u8,
+    pub eliminated: Option<Pubkey>,
+    /// Set once the poll is decided.
+    pub winners: Vec<Pubkey>,
+}
+
+#[event]
+pub struct ApprovalVoteCast {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub selections: Vec<Pubkey>,
+    pub total_votes: u64,
+}
+
+#[event]
+pub struct QuadraticVoteCast {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub allocations: Vec<QuadraticVote>,
+    pub credits_spent: u64,
+    pub total_votes: u64,
+}
+
+#[event]
+pub struct TokensDeposited {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub amount: u64,
+    pub escrowed: u64,
+}
+
+#[event]
+pub struct TokensWithdrawn {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct VoteChanged {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub previous_candidate: Pubkey,
+    pub candidate: Pubkey,
+    pub weight: u64,
+    pub previous_candidate_votes: u64,
+    pub candidate_votes: u64,
+}
+
+#[event]
+pub struct VoteRevoked {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub candidate: Pubkey,
+    pub weight: u64,
+    pub candidate_votes: u64,
+    pub total_votes: u64,
+}
+
+#[event]
+pub struct DelegationSet {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub poll_id: Option<u64>,
+}
+
+#[event]
+pub struct DelegationRemoved {
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub poll_id: Option<u64>,
+}
+
+#[event]
+pub struct DelegationOverridden {
+    pub poll_id: u64,
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub weight: u64,
+}
+
+#[event]
+pub struct PollFinalized {
+    pub poll_id: u64,
+    pub total_votes: u64,
+    pub winners: Vec<Pubkey>,
+    pub is_tie: bool,
+    pub unrevealed: u64,
+}
+
+#[event]
+pub struct VoteRecordClosed {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub payer: Pubkey,
+}
+
+#[event]
+pub struct CommitmentClosed {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+}
+
+#[event]
+pub struct CandidateClosed {
+    pub poll_id: u64,
+    pub candidate: Pubkey,
+    pub payer: Pubkey,
+}
+
+#[event]
+pub struct PollAccountClosed {
+    pub poll_id: u64,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct DelegatedVoteClosed {
+    pub poll_id: u64,
+    pub delegator: Pubkey,
+    pub payer: Pubkey,
+}
+
+#[event]
+pub struct RunoffClosed {
+    pub poll_id: u64,
+    pub payer: Pubkey,
+}
+
+#[event]
+pub struct EncryptedTallyClosed {
+    pub poll_id: u64,
+    pub payer: Pubkey,
+}
+
+#[event]
+pub struct VoterNonceClosed {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub payer: Pubkey,
+}
+
+#[event]
+pub struct VoteCommitted {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub commitment: [u8; 32],
+    pub commitments: u64,
+}
+
+#[event]
+pub struct VoteRevealed {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub candidate: Pubkey,
+    pub candidate_votes: u64,
+    pub total_votes: u64,
+}
+
+#[event]
+pub struct SessionCreated {
+    pub owner: Pubkey,
+    pub session_key: Pubkey,
+    pub expires_at: i64,
+    pub polls: Vec<u64>,
+}
+
+#[event]
+pub struct SessionRevoked {
+    pub owner: Pubkey,
+    pub session_key: Pubkey,
+}
+
+#[event]
+pub struct EncryptedTallyConfigured {
+    pub poll_id: u64,
+    pub trustees: Vec<Pubkey>,
+    pub threshold: u8,
+    pub candidate_ids: Vec<Pubkey>,
+}
+
+#[event]
+pub struct TrusteeKeyRegistered {
+    pub poll_id: u64,
+    pub trustee: Pubkey,
+    /// The trustee's coefficient commitments, against which the other trustees
+    /// check the shares it sends them.
+    pub commitments: Vec<[u8; 32]>,
+    pub public_key: [u8; 32],
+    /// Trustees still to register.
+    pub remaining: u8,
+}
+
+#[event]
+pub struct EncryptedVoteCast {
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub ciphertexts: Vec<Ciphertext>,
+    pub ballots: u64,
+}
+
+#[event]
+pub struct DecryptionSharePosted {
+    pub poll_id: u64,
+    pub trustee: Pubkey,
+    pub shares: Vec<[u8; 32]>,
+    /// Shares still needed before the tally can be decrypted.
+    pub remaining: u8,
+}
+
+#[event]
+pub struct EncryptedTallyDecrypted {
+    pub poll_id: u64,
+    pub counts: Vec<u64>,
+    pub total_votes: u64,
+}
+
+#[error_code]
+pub enum VotingError {
+    #[msg("Signer is not the poll authority")]
+    Unauthorized,
+    #[msg("Poll can no longer be edited once voting has started")]
+    PollAlreadyStarted,
+    #[msg("Poll start time must be before its end time")]
+    InvalidPollWindow,
+    #[msg("Voting has not started yet")]
+    PollNotStarted,
+    #[msg("Voting has ended")]
+    PollEnded,
+    #[msg("Poll has reached its maximum number of candidates")]
+    CandidateLimitReached,
+    #[msg("Candidate is not registered for this poll")]
+    UnknownCandidate,
+    #[msg("Candidate belongs to a different poll")]
+    CandidatePollMismatch,
+    #[msg("Poll does not accept self-nominated candidates")]
+    SelfNominationDisabled,
+    #[msg("Poll does not accept authority-registered candidates")]
+    AuthorityManagedDisabled,
+    #[msg("Candidate is not awaiting approval")]
+    CandidateNotPending,
+    #[msg("Candidate has not been approved")]
+    CandidateNotApproved,
+    #[msg("Name exceeds the maximum length")]
+    NameTooLong,
+    #[msg("Description exceeds the maximum length")]
+    DescriptionTooLong,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Poll status does not allow this transition")]
+    InvalidStatusTransition,
+    #[msg("Poll is not in draft")]
+    PollNotDraft,
+    #[msg("Poll is not open for voting")]
+    PollNotOpen,
+    #[msg("Poll has no registered candidates")]
+    NoCandidates,
+    #[msg("Poll end time has not passed yet")]
+    PollNotEnded,
+    #[msg("Candidate account supplied more than once")]
+    DuplicateCandidate,
+    #[msg("Not every registered candidate was supplied")]
+    IncompleteCandidates,
+    #[msg("Candidate tallies do not add up to the poll total")]
+    TallyMismatch,
+    #[msg("Poll has not been finalized")]
+    PollNotFinalized,
+    #[msg("All candidates must be closed before the poll")]
+    CandidatesNotClosed,
+    #[msg("Rent refund account does not match the original payer")]
+    PayerMismatch,
+    #[msg("Poll uses a different voting method")]
+    WrongVotingMethod,
+    #[msg("Ballot is malformed")]
+    InvalidBallot,
+    #[msg("Instant runoff must be decided before finalizing this poll")]
+    RunoffIncomplete,
+    #[msg("Ballot selects more candidates than the poll allows")]
+    TooManySelections,
+    #[msg("Poll configuration is invalid")]
+    InvalidPollConfig,
+    #[msg("Ballot costs more credits than the voter has")]
+    InsufficientCredits,
+    #[msg("Governance mint account is required for a token-weighted poll")]
+    MissingGovernanceMint,
+    #[msg("Token account is not for the poll's governance mint")]
+    GovernanceMintMismatch,
+    #[msg("Token escrow is required for a token-weighted poll")]
+    MissingEscrow,
+    #[msg("Voter has no voting weight")]
+    NoVotingWeight,
+    #[msg("Escrowed tokens back a counted vote")]
+    TokensLocked,
+    #[msg("NFT token account and metadata are required for a collection-gated poll")]
+    MissingNft,
+    #[msg("Voter does not hold the NFT")]
+    NftNotHeld,
+    #[msg("NFT is not a verified member of the poll's collection")]
+    NftNotInCollection,
+    #[msg("Vote record does not belong to this poll")]
+    VotePollMismatch,
+    #[msg("Eligibility proof is required for an allowlist poll")]
+    MissingEligibilityProof,
+    #[msg("Voter is not on the poll's allowlist")]
+    NotEligible,
+    #[msg("Vote is already for this candidate")]
+    AlreadyVotedFor,
+    #[msg("Delegation account is invalid for this vote")]
+    InvalidDelegation,
+    #[msg("Delegation would create a cycle")]
+    DelegationCycle,
+    #[msg("Delegation chain is too long")]
+    DelegationChainTooLong,
+    #[msg("Delegation chain accounts are incomplete")]
+    IncompleteDelegationChain,
+    #[msg("Poll does not support delegated votes")]
+    DelegationUnsupported,
+    #[msg("Delegator has already voted")]
+    DelegatorAlreadyVoted,
+    #[msg("The delegate's vote is required to override a delegation")]
+    MissingDelegateVote,
+    #[msg("Vote includes delegated weight")]
+    HasDelegators,
+    #[msg("Reveal window is not open")]
+    RevealNotOpen,
+    #[msg("Revealed vote does not match the commitment")]
+    CommitmentMismatch,
+    #[msg("Trustee key commitments or proof are invalid")]
+    InvalidTrusteeKey,
+    #[msg("Encrypted ballot proof is invalid")]
+    InvalidBallotProof,
+    #[msg("Signer is not a trustee of this poll")]
+    NotTrustee,
+    #[msg("Trustee has already posted decryption shares")]
+    SharePosted,
+    #[msg("Decryption share proof is invalid")]
+    InvalidDecryptionShare,
+    #[msg("Fewer trustees than the threshold have posted decryption shares")]
+    DecryptionIncomplete,
+    #[msg("Claimed totals do not match the encrypted tally")]
+    InvalidTally,
+    #[msg("Encrypted tally has not been decrypted")]
+    TallyNotDecrypted,
+    #[msg("Poll does not accept relayed votes")]
+    RelayUnsupported,
+    #[msg("Voter's weight was already cast by a delegate")]
+    DelegatedVoteCast,
+    #[msg("Ed25519 signature instruction must precede the vote")]
+    MissingSignature,
+    #[msg("Ed25519 signature instruction does not match the vote")]
+    InvalidSignature,
+    #[msg("Nonce is not the voter's next nonce")]
+    InvalidNonce,
+    #[msg("Session expiry must be in the future and within the maximum duration")]
+    InvalidSessionExpiry,
+    #[msg("Session lists too many polls")]
+    TooManySessionPolls,
+    #[msg("Session key has expired")]
+    SessionExpired,
+    #[msg("Session key is not valid for this poll")]
+    SessionOutOfScope,
+    #[msg("Poll id belongs to a finalized or cancelled poll")]
+    PollIdFinalized,
+    #[msg("Ballot has already been counted in this runoff round")]
+    BallotAlreadyCounted,
+    #[msg("Trustee has already registered its key")]
+    TrusteeRegistered,
+    #[msg("Not every trustee has registered its key")]
+    TrusteeKeysIncomplete,
+    #[msg("Quadratic ballots require the voter's credits account")]
+    MissingCredits,
+    #[msg("Instant runoff has already been decided")]
+    RunoffDecided,
 }
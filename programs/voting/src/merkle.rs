@@ -0,0 +1,88 @@
+//! Merkle allowlists of eligible voters.
+//!
+//! A leaf commits to a voter and the weight attached to them in the snapshot
+//! (1 for unweighted snapshots). Pairs are hashed in sorted order, so a proof
+//! is just the list of sibling hashes from leaf to root. `MerkleTree` builds
+//! roots and proofs off-chain with the same hashing `vote` verifies against.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+// Distinct prefixes keep a leaf from being passed off as an inner node.
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// The leaf committing to `voter` and their snapshot `weight`.
+pub fn leaf(voter: &Pubkey, weight: u64) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, voter.as_ref(), &weight.to_le_bytes()]).to_bytes()
+}
+
+fn node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Whether `proof` leads from `leaf` to `root`.
+pub fn verify(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    proof.iter().fold(leaf, |hash, sibling| node(&hash, sibling)) == *root
+}
+
+/// An allowlist tree, built off-chain from `(voter, weight)` entries.
+///
+/// ```
+/// use anchor_lang::prelude::Pubkey;
+/// use voting::merkle::{self, MerkleTree};
+///
+/// let entries: Vec<_> = (1..=5).map(|weight| (Pubkey::new_unique(), weight)).collect();
+/// let tree = MerkleTree::new(&entries);
+/// let (voter, weight) = entries[4];
+/// let proof = tree.proof(4).unwrap();
+/// assert!(merkle::verify(&tree.root(), merkle::leaf(&voter, weight), &proof));
+/// ```
+pub struct MerkleTree {
+    /// Hashes of every level, leaves first and the root last.
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    /// Builds the tree over `entries` in the given order. An unpaired hash at
+    /// the end of a level is carried up unchanged.
+    pub fn new(entries: &[(Pubkey, u64)]) -> Self {
+        let mut levels = vec![entries.iter().map(|(voter, weight)| leaf(voter, *weight)).collect::<Vec<_>>()];
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => node(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    /// The root to store in the poll, or all zeroes for an empty tree.
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().and_then(|level| level.first()).copied().unwrap_or_default()
+    }
+
+    /// The proof for the entry at `index`, or `None` if out of range.
+    pub fn proof(&self, index: usize) -> Option<Vec<[u8; 32]>> {
+        if index >= self.levels[0].len() {
+            return None;
+        }
+        let mut proof = Vec::new();
+        let mut index = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        Some(proof)
+    }
+}
@@ -0,0 +1,70 @@
+//! Ballots signed off-chain by the voter and submitted by a relayer.
+//!
+//! The voter signs `message(poll_id, candidate_id, nonce)` with their wallet
+//! key. The relayer puts a native Ed25519 program instruction verifying that
+//! signature immediately before `vote_with_signature`, which reads it back
+//! through the instructions sysvar; the runtime has already rejected the
+//! transaction if the signature itself was bad.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::sysvar::instructions::get_instruction_relative;
+
+use crate::VotingError;
+
+/// Prefix of every signed ballot, so the signature cannot be replayed as
+/// anything but a vote in this program.
+pub const DOMAIN: &[u8] = b"voting:vote:v1";
+
+// Layout of an Ed25519 program instruction with its data inline: a signature
+// count and padding byte, one offsets record, then the referenced bytes.
+const OFFSETS_START: usize = 2;
+const OFFSETS_LEN: usize = 14;
+const PUBKEY_LEN: usize = 32;
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// The bytes a voter signs to vote for `candidate_id` in poll `poll_id`, with
+/// `nonce` the voter's next nonce in that poll (see `VoterNonce`).
+pub fn message(poll_id: u64, candidate_id: &Pubkey, nonce: u64) -> Vec<u8> {
+    [
+        DOMAIN,
+        crate::ID.as_ref(),
+        &poll_id.to_le_bytes(),
+        candidate_id.as_ref(),
+        &nonce.to_le_bytes(),
+    ]
+    .concat()
+}
+
+/// Requires the instruction before the current one to verify exactly one
+/// signature by `signer` over `message`, with everything in its own data.
+pub fn verify_signature(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let instruction = get_instruction_relative(-1, instructions).map_err(|_| error!(VotingError::MissingSignature))?;
+    require_keys_eq!(instruction.program_id, ed25519_program::ID, VotingError::MissingSignature);
+    let data = instruction.data.as_slice();
+    require!(
+        instruction.accounts.is_empty() && data.len() >= OFFSETS_START + OFFSETS_LEN && data[0] == 1,
+        VotingError::InvalidSignature
+    );
+
+    let offsets: Vec<u16> = data[OFFSETS_START..OFFSETS_START + OFFSETS_LEN]
+        .chunks_exact(2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .collect();
+    let [_, signature_index, pubkey_offset, pubkey_index, message_offset, message_len, message_index] = offsets[..] else {
+        return err!(VotingError::InvalidSignature);
+    };
+    // Data referenced from another instruction could say anything.
+    require!(
+        [signature_index, pubkey_index, message_index].iter().all(|index| *index == CURRENT_INSTRUCTION),
+        VotingError::InvalidSignature
+    );
+
+    let slice = |offset: u16, len: usize| data.get(offset as usize..offset as usize + len);
+    require!(
+        slice(pubkey_offset, PUBKEY_LEN) == Some(signer.as_ref()),
+        VotingError::InvalidSignature
+    );
+    require!(slice(message_offset, message_len as usize) == Some(message), VotingError::InvalidSignature);
+    Ok(())
+}